@@ -0,0 +1,305 @@
+#!/usr/bin/env python3
+"""
+Makes DejaVuSans-Arabic.ttf: just the Arabic letters from DejaVu Sans, for the
+fonts egui doesn't have letters for. SGS shapes Arabic itself, using the
+presentation forms, so the font doesn't need any OpenType layout tables.
+
+Without those tables, nothing puts vowel marks on their letters, so the marks are
+moved here to sit over the letter drawn before them, like DejaVu's Latin accents.
+
+Only needs Python's standard library, so it works without fontTools.
+
+    python3 subset.py /usr/share/fonts/truetype/dejavu/DejaVuSans.ttf DejaVuSans-Arabic.ttf
+"""
+
+import struct
+import sys
+
+# Arabic, Arabic Presentation Forms-A and -B.
+RANGES = [(0x0600, 0x06FF), (0xFB50, 0xFDFF), (0xFE70, 0xFEFF)]
+# Marks that go on the letter before them.
+MARKS = [(0x0610, 0x061A), (0x064B, 0x065F), (0x0670, 0x0670), (0x06D6, 0x06ED)]
+
+KEEP_TABLES = [b"OS/2", b"cmap", b"glyf", b"head", b"hhea", b"hmtx", b"loca", b"maxp", b"name", b"post"]
+
+# Simple glyph flags.
+X_SHORT_VECTOR = 0x02
+REPEAT_FLAG = 0x08
+X_IS_SAME_OR_POSITIVE = 0x10
+
+# Composite glyph flags.
+ARG_1_AND_2_ARE_WORDS = 0x0001
+ARGS_ARE_XY_VALUES = 0x0002
+WE_HAVE_A_SCALE = 0x0008
+MORE_COMPONENTS = 0x0020
+WE_HAVE_AN_X_AND_Y_SCALE = 0x0040
+WE_HAVE_A_TWO_BY_TWO = 0x0080
+
+
+def read_tables(data):
+    num_tables = struct.unpack(">H", data[4:6])[0]
+    tables = {}
+    for i in range(num_tables):
+        tag, _, offset, length = struct.unpack(">4sIII", data[12 + 16 * i:28 + 16 * i])
+        tables[tag] = data[offset:offset + length]
+    return tables
+
+
+def read_cmap(cmap):
+    """Codepoint => glyph id, from the format 4 Unicode BMP subtable."""
+    num_subtables = struct.unpack(">H", cmap[2:4])[0]
+    for i in range(num_subtables):
+        platform, encoding, offset = struct.unpack(">HHI", cmap[4 + 8 * i:12 + 8 * i])
+        if (platform, encoding) in [(3, 1), (0, 3)] and struct.unpack(">H", cmap[offset:offset + 2])[0] == 4:
+            break
+    else:
+        sys.exit("No format 4 Unicode cmap subtable")
+
+    sub = cmap[offset:]
+    seg_count = struct.unpack(">H", sub[6:8])[0] // 2
+    ends = struct.unpack(">%dH" % seg_count, sub[14:14 + 2 * seg_count])
+    starts_at = 16 + 2 * seg_count
+    starts = struct.unpack(">%dH" % seg_count, sub[starts_at:starts_at + 2 * seg_count])
+    deltas_at = starts_at + 2 * seg_count
+    deltas = struct.unpack(">%dh" % seg_count, sub[deltas_at:deltas_at + 2 * seg_count])
+    range_offsets_at = deltas_at + 2 * seg_count
+
+    mapping = {}
+    for seg in range(seg_count):
+        range_offset = struct.unpack(">H", sub[range_offsets_at + 2 * seg:range_offsets_at + 2 * seg + 2])[0]
+        for code in range(starts[seg], ends[seg] + 1):
+            if code == 0xFFFF:
+                continue
+            if range_offset == 0:
+                glyph = (code + deltas[seg]) & 0xFFFF
+            else:
+                at = range_offsets_at + 2 * seg + range_offset + 2 * (code - starts[seg])
+                glyph = struct.unpack(">H", sub[at:at + 2])[0]
+                if glyph:
+                    glyph = (glyph + deltas[seg]) & 0xFFFF
+            if glyph:
+                mapping[code] = glyph
+    return mapping
+
+
+def components(glyph):
+    """(offset of each component's glyph index, that index) in a composite glyph."""
+    if len(glyph) < 10 or struct.unpack(">h", glyph[0:2])[0] >= 0:
+        return []
+    found = []
+    at = 10
+    while True:
+        flags, index = struct.unpack(">HH", glyph[at:at + 4])
+        found.append((at + 2, index))
+        at += 4 + (4 if flags & ARG_1_AND_2_ARE_WORDS else 2)
+        if flags & WE_HAVE_A_SCALE:
+            at += 2
+        elif flags & WE_HAVE_AN_X_AND_Y_SCALE:
+            at += 4
+        elif flags & WE_HAVE_A_TWO_BY_TWO:
+            at += 8
+        if not flags & MORE_COMPONENTS:
+            return found
+
+
+def transform_size(flags):
+    if flags & WE_HAVE_A_SCALE:
+        return 2
+    if flags & WE_HAVE_AN_X_AND_Y_SCALE:
+        return 4
+    if flags & WE_HAVE_A_TWO_BY_TWO:
+        return 8
+    return 0
+
+
+def shift(glyph, dx):
+    """`glyph`, moved `dx` units to the right."""
+    contours, x_min, y_min, x_max, y_max = struct.unpack(">hhhhh", glyph[:10])
+    header = struct.pack(">hhhhh", contours, x_min + dx, y_min, x_max + dx, y_max)
+
+    if contours < 0:
+        out = header
+        at = 10
+        while True:
+            flags, index = struct.unpack(">HH", glyph[at:at + 4])
+            if not flags & ARGS_ARE_XY_VALUES:
+                sys.exit("Can't move a component that's placed by matching points")
+            if flags & ARG_1_AND_2_ARE_WORDS:
+                x, y = struct.unpack(">hh", glyph[at + 4:at + 8])
+                at += 8
+            else:
+                x, y = struct.unpack(">bb", glyph[at + 4:at + 6])
+                at += 6
+            size = transform_size(flags)
+            out += struct.pack(">HHhh", flags | ARG_1_AND_2_ARE_WORDS, index, x + dx, y) + glyph[at:at + size]
+            at += size
+            if not flags & MORE_COMPONENTS:
+                # Instructions, if any.
+                return out + glyph[at:]
+
+    at = 10 + 2 * contours
+    points = struct.unpack(">H", glyph[at - 2:at])[0] + 1 if contours else 0
+    at += 2 + struct.unpack(">H", glyph[at:at + 2])[0]
+    instructions_end = at
+
+    flags = []
+    while len(flags) < points:
+        flag = glyph[at]
+        at += 1
+        flags.append(flag)
+        if flag & REPEAT_FLAG:
+            flags.extend([flag] * glyph[at])
+            at += 1
+
+    xs = []
+    x = 0
+    for flag in flags:
+        if flag & X_SHORT_VECTOR:
+            x += glyph[at] if flag & X_IS_SAME_OR_POSITIVE else -glyph[at]
+            at += 1
+        elif not flag & X_IS_SAME_OR_POSITIVE:
+            x += struct.unpack(">h", glyph[at:at + 2])[0]
+            at += 2
+        xs.append(x)
+
+    # Every x as a two-byte delta, so the flags can't repeat any more.
+    new_flags = bytes(flag & ~(X_SHORT_VECTOR | REPEAT_FLAG | X_IS_SAME_OR_POSITIVE) for flag in flags)
+    new_xs = b""
+    previous = 0
+    for x in xs:
+        new_xs += struct.pack(">h", x + dx - previous)
+        previous = x + dx
+    return header + glyph[10:instructions_end] + new_flags + new_xs + glyph[at:]
+
+
+def build_cmap(mapping):
+    """A format 4 subtable for (3, 1), with one segment per run of codepoints."""
+    codes = sorted(mapping)
+    segments = []
+    for code in codes:
+        if segments and segments[-1][1] == code - 1 and mapping[code] - code == mapping[segments[-1][0]] - segments[-1][0]:
+            segments[-1][1] = code
+        else:
+            segments.append([code, code])
+    segments.append([0xFFFF, 0xFFFF])
+
+    seg_count = len(segments)
+    search_range = 2 ** (seg_count.bit_length() - 1) * 2
+    entry_selector = seg_count.bit_length() - 1
+    range_shift = 2 * seg_count - search_range
+
+    ends = b"".join(struct.pack(">H", end) for _, end in segments)
+    starts = b"".join(struct.pack(">H", start) for start, _ in segments)
+    deltas = b"".join(struct.pack(">H", (mapping[start] - start) & 0xFFFF if start != 0xFFFF else 1) for start, _ in segments)
+    range_offsets = b"\0\0" * seg_count
+    body = struct.pack(">HHHH", seg_count * 2, search_range, entry_selector, range_shift) + ends + b"\0\0" + starts + deltas + range_offsets
+    subtable = struct.pack(">HHH", 4, 6 + len(body), 0) + body
+    return struct.pack(">HHHHI", 0, 1, 3, 1, 12) + subtable
+
+
+def checksum(data):
+    data += b"\0" * (-len(data) % 4)
+    return sum(struct.unpack(">%dI" % (len(data) // 4), data)) & 0xFFFFFFFF
+
+
+def write_font(tables):
+    tags = sorted(tables)
+    num_tables = len(tags)
+    entry_selector = num_tables.bit_length() - 1
+    search_range = 2 ** entry_selector * 16
+    header = struct.pack(">IHHHH", 0x00010000, num_tables, search_range, entry_selector, num_tables * 16 - search_range)
+
+    offset = 12 + 16 * num_tables
+    directory = b""
+    body = b""
+    for tag in tags:
+        data = tables[tag]
+        directory += struct.pack(">4sIII", tag, checksum(data), offset + len(body), len(data))
+        body += data + b"\0" * (-len(data) % 4)
+    font = header + directory + body
+
+    # head.checkSumAdjustment makes the whole font sum to a magic number.
+    head_at = offset + sum(len(tables[t]) + (-len(tables[t]) % 4) for t in tags[:tags.index(b"head")])
+    adjustment = (0xB1B0AFBA - checksum(font)) & 0xFFFFFFFF
+    return font[:head_at + 8] + struct.pack(">I", adjustment) + font[head_at + 12:]
+
+
+def main(source, output):
+    data = open(source, "rb").read()
+    tables = read_tables(data)
+
+    num_glyphs = struct.unpack(">H", tables[b"maxp"][4:6])[0]
+    long_loca = struct.unpack(">h", tables[b"head"][50:52])[0] == 1
+    if long_loca:
+        loca = struct.unpack(">%dI" % (num_glyphs + 1), tables[b"loca"][:4 * (num_glyphs + 1)])
+    else:
+        loca = [2 * x for x in struct.unpack(">%dH" % (num_glyphs + 1), tables[b"loca"][:2 * (num_glyphs + 1)])]
+    glyphs = [tables[b"glyf"][loca[i]:loca[i + 1]] for i in range(num_glyphs)]
+
+    num_metrics = struct.unpack(">H", tables[b"hhea"][34:36])[0]
+    hmtx = tables[b"hmtx"]
+    def metrics(glyph):
+        advance = struct.unpack(">H", hmtx[4 * min(glyph, num_metrics - 1):4 * min(glyph, num_metrics - 1) + 2])[0]
+        if glyph < num_metrics:
+            lsb = struct.unpack(">h", hmtx[4 * glyph + 2:4 * glyph + 4])[0]
+        else:
+            at = 4 * num_metrics + 2 * (glyph - num_metrics)
+            lsb = struct.unpack(">h", hmtx[at:at + 2])[0]
+        return struct.pack(">Hh", advance, lsb)
+
+    mapping = {code: glyph for code, glyph in read_cmap(tables[b"cmap"]).items()
+               if any(start <= code <= end for start, end in RANGES)}
+
+    # .notdef, the mapped glyphs, and whatever they're made of.
+    keep = {0}
+    todo = list(mapping.values())
+    while todo:
+        glyph = todo.pop()
+        if glyph not in keep:
+            keep.add(glyph)
+            todo.extend(index for _, index in components(glyphs[glyph]))
+    old_ids = sorted(keep)
+    new_id = {old: new for new, old in enumerate(old_ids)}
+
+    # Centered over the letter before them, taking a typical letter's width.
+    marks = {glyph for code, glyph in mapping.items() if any(start <= code <= end for start, end in MARKS)}
+    advances = sorted(struct.unpack(">H", metrics(glyph)[:2])[0] for glyph in mapping.values() if glyph not in marks)
+    center = -advances[len(advances) // 2] // 2
+    for glyph in marks:
+        if glyphs[glyph]:
+            x_min, x_max = struct.unpack(">hxxh", glyphs[glyph][2:8])
+            glyphs[glyph] = shift(glyphs[glyph], center - (x_min + x_max) // 2)
+
+    glyf = b""
+    new_loca = [0]
+    for old in old_ids:
+        glyph = bytearray(glyphs[old])
+        for at, index in components(glyphs[old]):
+            glyph[at:at + 2] = struct.pack(">H", new_id[index])
+        glyf += bytes(glyph) + b"\0" * (-len(glyph) % 4)
+        new_loca.append(len(glyf))
+
+    out = {tag: tables[tag] for tag in KEEP_TABLES}
+    out[b"glyf"] = glyf
+    out[b"loca"] = struct.pack(">%dI" % len(new_loca), *new_loca)
+    # Marks still don't take up any room; their left side bearing is where they start now.
+    out[b"hmtx"] = b"".join(struct.pack(">Hh", 0, struct.unpack(">h", glyphs[old][2:4])[0]) if old in marks and glyphs[old] else metrics(old)
+                            for old in old_ids)
+    out[b"cmap"] = build_cmap({code: new_id[glyph] for code, glyph in mapping.items()})
+    head = bytearray(tables[b"head"])
+    head[8:12] = b"\0\0\0\0"
+    head[50:52] = struct.pack(">h", 1)
+    out[b"head"] = bytes(head)
+    out[b"hhea"] = tables[b"hhea"][:34] + struct.pack(">H", len(old_ids))
+    out[b"maxp"] = tables[b"maxp"][:4] + struct.pack(">H", len(old_ids)) + tables[b"maxp"][6:]
+    # Version 3: no glyph names, which would all be wrong now.
+    out[b"post"] = struct.pack(">I", 0x00030000) + tables[b"post"][4:32]
+
+    open(output, "wb").write(write_font(out))
+    print("Kept %d of %d glyphs, for %d characters. Moved %d marks to x = %d." % (len(old_ids), num_glyphs, len(mapping), len(marks), center))
+
+
+if __name__ == "__main__":
+    if len(sys.argv) != 3:
+        sys.exit(__doc__)
+    main(sys.argv[1], sys.argv[2])
@@ -0,0 +1,87 @@
+use std::fmt;
+
+/// Something a button can do instead of adding a word to the panel.
+///
+/// In a System file, actions are written as strings: e.g. `"speak"`,
+/// `"say: Excuse me!"`, or `"toggle: speak_each_word"`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Action {
+    Speak,
+    Clear,
+    DeleteWord,
+    Back,
+    Home,
+    OpenKeyboard,
+    ToggleSetting(String),
+    /// Speak a fixed phrase, without touching the panel.
+    Say(String),
+    SetVoice(String),
+    SetTheme(String),
+    /// Run one of the System's saved macros.
+    RunMacro(String),
+    /// Pause or resume the activity log. Works while locked, so the user can always
+    /// stop what they say from being logged.
+    PauseLogging,
+}
+
+impl Action {
+    pub fn parse(action: &str) -> Option<Self> {
+        let (name, arg) =
+            if let Some((name, arg)) = action.split_once(':') {
+                (name.trim(), Some(arg.trim().to_string()))
+            } else {
+                (action.trim(), None)
+            };
+
+        match (name, arg) {
+            ("speak", None) => Some(Self::Speak),
+            ("clear", None) => Some(Self::Clear),
+            ("delete-word", None) => Some(Self::DeleteWord),
+            ("back", None) => Some(Self::Back),
+            ("home", None) => Some(Self::Home),
+            ("keyboard", None) => Some(Self::OpenKeyboard),
+            ("pause-logging", None) => Some(Self::PauseLogging),
+            ("toggle", Some(setting)) if !setting.is_empty() => Some(Self::ToggleSetting(setting)),
+            ("say", Some(phrase)) if !phrase.is_empty() => Some(Self::Say(phrase)),
+            ("voice", Some(voice)) if !voice.is_empty() => Some(Self::SetVoice(voice)),
+            ("theme", Some(theme)) if !theme.is_empty() => Some(Self::SetTheme(theme)),
+            ("macro", Some(name)) if !name.is_empty() => Some(Self::RunMacro(name)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Speak => write!(f, "speak"),
+            Self::Clear => write!(f, "clear"),
+            Self::DeleteWord => write!(f, "delete-word"),
+            Self::Back => write!(f, "back"),
+            Self::Home => write!(f, "home"),
+            Self::OpenKeyboard => write!(f, "keyboard"),
+            Self::ToggleSetting(setting) => write!(f, "toggle: {}", setting),
+            Self::Say(phrase) => write!(f, "say: {}", phrase),
+            Self::SetVoice(voice) => write!(f, "voice: {}", voice),
+            Self::SetTheme(theme) => write!(f, "theme: {}", theme),
+            Self::RunMacro(name) => write!(f, "macro: {}", name),
+            Self::PauseLogging => write!(f, "pause-logging"),
+        }
+    }
+}
+
+#[test]
+fn test_action() {
+    assert_eq!(Some(Action::Speak), Action::parse("speak"));
+    assert_eq!(Some(Action::DeleteWord), Action::parse(" delete-word "));
+    assert_eq!(Some(Action::Say("Excuse me: hi!".to_string())), Action::parse("say: Excuse me: hi!"));
+    assert_eq!(Some(Action::RunMacro("greet".to_string())), Action::parse("macro:greet"));
+
+    assert_eq!(None, Action::parse("speak: now"));
+    assert_eq!(None, Action::parse("say:"));
+    assert_eq!(None, Action::parse("dance"));
+
+    for action in ["speak", "clear", "delete-word", "back", "home", "keyboard", "toggle: x", "say: hi", "voice: x", "theme: x", "macro: x", "pause-logging"] {
+        assert_eq!(action, Action::parse(action).unwrap().to_string());
+    }
+}
@@ -1,281 +1,1905 @@
 use log::{info, warn}; // also available: trace.
 use eframe::egui;
 use eframe::egui::Pos2;
+use nanoserde::SerJson;
 
+use crate::action::Action;
+use crate::button::Button;
+use crate::color::Colors;
+use crate::editor::{self, Editor};
+use crate::events::{Event, Listener};
+use crate::i18n::{self, Catalog};
 use crate::system::System;
+use crate::lam::{self, Lam, Source};
+use crate::layout::{self, HotbarPosition, Layout, Region};
+use crate::loader::{self, Loader};
+use crate::lock::Lock;
+use crate::navigation::{self, Modeling, Navigation, Step};
 use crate::panel::Panel;
+use crate::profile::{self, Profile};
+use crate::search::SearchIndex;
+use crate::settings::Settings;
 use crate::speech::SpeechEngine;
+use crate::theme::{self, Theme};
+use crate::users::{UserStorage, Users};
 use crate::cli;
 
 use std::cmp;
 
-pub const MIN_WIDTH: f32 = 1280.0;
-pub const MIN_HEIGHT: f32 = 720.0;
+// Small enough for a phone, or a display mounted on a wheelchair.
+pub const MIN_WIDTH: f32 = 320.0;
+pub const MIN_HEIGHT: f32 = 320.0;
+
+// Room for the Back/Home buttons and the breadcrumb above the panel.
+const BREADCRUMB_HEIGHT: f32 = 24.0;
+
+// Macros can run other macros; this keeps one that runs itself from hanging the app.
+const MAX_MACRO_DEPTH: usize = 8;
+
+// Without a PIN, the editor opens after holding its button this long.
+const EDIT_HOLD_SECONDS: f64 = 2.0;
+
+// Without a PIN, unlocking takes holding the lock button this long.
+const UNLOCK_HOLD_SECONDS: f64 = 3.0;
+
+// How far the drawer handle has to be dragged to open or close it.
+const DRAWER_SWIPE: f32 = 20.0;
+
+const KEYBOARD_ROWS: [&str; 4] = ["1234567890", "qwertyuiop", "asdfghjkl'", "zxcvbnm,.?"];
 
 #[derive(Debug)]
 struct Dimensions {
     item_spacing: f32,
     margin: f32,
     button_size: [f32; 2],
+    // Speak, Delete, Clear and panel entries.
+    top_size: [f32; 2],
+    panel_width: f32,
+    portrait: bool,
 }
 
 impl Dimensions {
-    pub fn new(screen_size: Pos2, rows: usize, cols: usize) -> Self {
+    pub fn new(screen_size: Pos2, rows: usize, cols: usize, layout: &Layout) -> Self {
         let width = screen_size.x;
         let height = screen_size.y;
 
-        let item_spacing = 5.0;
-        let margin = 10.0;
+        let item_spacing = layout.spacing;
+        let margin = layout.margin;
 
-        let button_width = ((width - margin - item_spacing) / ((cols as f32) + 3.0)) - item_spacing;
-        let button_height = ((height - margin - item_spacing) / ((rows as f32) + 2.0)) - item_spacing;
+        let portrait = layout.is_portrait(width, height);
+        let (width_units, height_units) =
+            if portrait {
+                (cols as f32, layout.portrait_height_units(rows))
+            } else {
+                (layout.width_units(cols), layout.height_units(rows))
+            };
+
+        // Past a point, scroll rather than shrink buttons further.
+        let button_width = (((width - margin - item_spacing) / width_units.max(1.0)) - item_spacing).max(layout.min_target);
+        let button_height = (((height - margin - item_spacing - BREADCRUMB_HEIGHT) / height_units.max(1.0)) - item_spacing).max(layout.min_target);
 
         let button_size = [button_width, button_height];
+        let top_size = [button_width, button_height * layout.top_height];
+
+        // In portrait, the panel shares the board's width with Speak, Delete and Clear.
+        let panel_cols =
+            if portrait {
+                cmp::max(1, cols.saturating_sub(layout.portrait_top_row().len().saturating_sub(1)))
+            } else {
+                cols
+            };
+        let panel_width = (panel_cols as f32) * (button_width + item_spacing);
+
+        Self { item_spacing, margin, button_size, top_size, panel_width, portrait }
+    }
+
+    // A button `width` board buttons wide.
+    pub fn sized(&self, width: f32) -> [f32; 2] {
+        [self.button_size[0] * width, self.button_size[1]]
+    }
+}
+
+// Pulses the border, so it catches the eye without moving anything around.
+fn highlighted<'a>(egui_button: egui::Button<'a>, ui: &egui::Ui, theme: &Theme) -> egui::Button<'a> {
+    let time = ui.input(|i| i.time);
+    let width = theme.highlight_width * (1.0 + (time * 3.0).sin().abs() as f32);
+    ui.ctx().request_repaint();
+
+    egui_button.selected(true).stroke(egui::Stroke::new(width, theme.highlight_color(ui.visuals())))
+}
+
+// Whether `response` has been held down for `seconds`. `started` tracks when the hold began.
+fn held(started: &mut Option<f64>, ui: &egui::Ui, response: &egui::Response, seconds: f64) -> bool {
+    if !response.is_pointer_button_down_on() {
+        *started = None;
+        return false;
+    }
+
+    let now = ui.input(|i| i.time);
+    let since = *started.get_or_insert(now);
+    ui.ctx().request_repaint();
+    if now - since >= seconds {
+        *started = None;
+        true
+    } else {
+        false
+    }
+}
 
-        Self { item_spacing, margin, button_size }
+// An editable text field for an optional value; empty means None. Returns whether it changed.
+fn optional_text(ui: &mut egui::Ui, label: &str, value: &mut Option<String>) -> bool {
+    ui.label(label);
+    let mut text = value.clone().unwrap_or_default();
+    if ui.text_edit_singleline(&mut text).changed() {
+        *value = Some(text).filter(|t| !t.is_empty());
+        true
+    } else {
+        false
     }
 }
 
+// A button drawn in its color-scheme colors. Anything unset keeps the theme's look.
+fn colored(label: impl Into<String>, colors: &Colors, theme: &Theme) -> egui::Button<'static> {
+    let mut text = egui::RichText::new(i18n::display(&label.into()));
+    if let Some(color) = colors.text() {
+        text = text.color(color);
+    }
+
+    let mut egui_button = egui::Button::new(text);
+    if let Some(background) = colors.background() {
+        egui_button = egui_button.fill(background);
+    }
+    if let Some(border) = colors.border() {
+        egui_button = egui_button.stroke(egui::Stroke::new(theme.border_width.max(2.0), border));
+    }
+
+    egui_button
+}
+
+#[derive(Debug, PartialEq)]
+enum View {
+    Board,
+    Search,
+    Keyboard,
+    Settings,
+}
+
 pub struct App {
     speech_engine: SpeechEngine,
     panel: Panel,
     system: System,
-    current_folder: usize,
-    current_page: usize,
+    settings: Settings,
+    profile: Profile,
+    // Taps show or hide buttons for this user, instead of pressing them.
+    editing_visibility: bool,
+    theme: Theme,
+    // Set when the theme changes, so it's applied on the next frame.
+    restyle: bool,
+    view: View,
+    nav: Navigation,
     current_hotbar_page: usize,
+    // (folder, button index) to draw attention to, e.g. after "show me where it lives".
+    highlight: Option<(usize, usize)>,
+    modeling: Option<Modeling>,
+    search_index: SearchIndex,
+    search_query: String,
+    focus_search: bool,
+    keyboard_text: String,
+    drawer_open: bool,
+    // How far the drawer handle has been dragged so far; negative is up.
+    drawer_swipe: f32,
+    // The file the System came from, if any; the editor saves back to it.
+    system_file: Option<String>,
+    // The System given with --system, for users who haven't imported their own.
+    launch_system_file: Option<String>,
+    editor: Option<Editor>,
+    pin_entry: String,
+    // When the "hold to edit" or "hold to unlock" button started being held.
+    hold_started: Option<f64>,
+    lock: Lock,
+    // Showing the PIN entry for unlocking.
+    unlocking: bool,
+    // Whether the window decorations were last hidden for the lock.
+    #[cfg(not(target_arch = "wasm32"))]
+    decorations_locked: Option<bool>,
+    loader: Loader,
+    // Why the last System we tried to load couldn't be used, until it's dismissed.
+    system_error: Option<String>,
+    // JSON of a newly imported System to keep for next time, or "" to forget it.
+    store_system: Option<String>,
+    // Events from this frame, handed to the listeners at the end of it.
+    events: Vec<Event>,
+    listeners: Vec<Listener>,
+    // Id of the folder listeners were last told about.
+    shown_folder: Option<String>,
+    // For redrawing after changes from outside, e.g. JavaScript.
+    ctx: egui::Context,
+    // Switches to a newer version that's ready, e.g. of the web app, once the user agrees.
+    apply_update: Option<Box<dyn FnOnce()>>,
+    // Whether the "update available" prompt was put off until later.
+    update_dismissed: bool,
+    users: Users,
+    // Showing the "who's using SGS?" picker instead of the board.
+    picking_user: bool,
+    // User to switch to on the next update, once there's storage to load them from.
+    switch_user: Option<usize>,
+    new_user_name: String,
+    lam: Lam,
+    redact_word: String,
+    // Where the activity log was last exported to, or why it couldn't be.
+    lam_status: Option<String>,
+    // Every language on offer, and the one in use.
+    catalogs: Vec<Catalog>,
+    catalog: Catalog,
+}
+
+/// Speaks `text`, and lets listeners know.
+fn speak(speech_engine: &mut SpeechEngine, events: &mut Vec<Event>, text: String) {
+    match speech_engine.speak(text.as_str()) {
+        Ok(()) if !text.is_empty() => events.push(Event::Spoken(text)),
+        Ok(()) => {},
+        Err(err) => warn!("Failed to speak: {}", err),
+    }
+}
+
+fn apply_speech_settings(speech_engine: &mut SpeechEngine, settings: &Settings) {
+    if let Some(voice) = &settings.voice {
+        if let Err(err) = speech_engine.set_voice(voice) {
+            warn!("Could not restore voice: {}", err);
+        }
+    }
+    if let Err(err) = speech_engine.set_rate(settings.rate).and_then(|_| speech_engine.set_pitch(settings.pitch)) {
+        warn!("Could not restore speaking rate and pitch: {}", err);
+    }
+}
+
+/// The System this user imported, if any.
+fn imported_system(storage: Option<&dyn eframe::Storage>) -> Option<System> {
+    let json = storage.and_then(|s| s.get_string(loader::STORAGE_KEY)).filter(|json| !json.is_empty())?;
+    System::load_checked(&json).map_err(|err| {
+        warn!("!!! Failed to load the imported System, using the bundled one: {}", err);
+    }).ok()
+}
+
+/// The System a user sees: the one they imported, or else the one given with --system,
+/// or else the bundled one. Also returns the file it came from, if any.
+fn user_system(storage: Option<&dyn eframe::Storage>, launch_system_file: Option<&String>) -> Result<(System, Option<String>), String> {
+    if let Some(system) = imported_system(storage) {
+        return Ok((system, None));
+    }
+    match launch_system_file {
+        Some(path) => System::load_file(path)
+            .map(|system| (system, Some(path.clone())))
+            .map_err(|err| format!("Failed to load System from '{}': {}", path, err)),
+        None => Ok((bundled_system(), None)),
+    }
+}
+
+fn bundled_system() -> System {
+    System::load_str(include_str!("../system-wiki2016.json")).expect("Failed to load bundled System. (This should never happen.)")
 }
 
 impl App {
-    pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
+    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
         // Customize egui here with cc.egui_ctx.set_fonts and cc.egui_ctx.set_visuals.
         // Restore app state using cc.storage (requires the "persistence" feature).
         // Use the cc.gl (a glow::Context) to create graphics shaders and buffers that you can use
         // for e.g. egui::PaintCallback.
-        let speech_engine = SpeechEngine::default().expect("Failed to initialize text-to-speech system");
-        let panel = Panel::default();
+        let mut speech_engine = SpeechEngine::default().unwrap_or_else(|err| {
+            // Better to have a board that can't speak than no board at all.
+            warn!("!!! Failed to initialize text-to-speech system, speech is disabled: {}", err);
+            SpeechEngine::silent()
+        });
+        let ctx = cc.egui_ctx.clone();
+        speech_engine.on_change(move || ctx.request_repaint());
+
+        let users = Users::load(cc.storage);
+        let user_storage = cc.storage.map(|s| UserStorage::new(s, users.current()));
+        let storage = user_storage.as_ref().map(|s| s as &dyn eframe::Storage);
+
+        let settings = Settings::load(storage);
+        apply_speech_settings(&mut speech_engine, &settings);
+
+        let profile = Profile::load(storage);
+        let lock = Lock::load(storage);
 
         let args = cli::parse_args().unwrap();
 
-        let system: System =
-            if let Some(system_file) = args.system {
-                System::load_file(&system_file).expect("Failed to load System from specified file")
-            } else {
-                System::load_str(include_str!("../system-wiki2016.json")).expect("Failed to load bundled System. (This should never happen.)")
-            };
+        let (system, system_file) = user_system(storage, args.system.as_ref()).unwrap_or_else(|err| panic!("{}", err));
         info!("Loaded System configuration.");
 
-        let current_folder = 0;
-        let current_page = 0;
+        for problem in system.color_problems() {
+            warn!("Color problem: {}", problem);
+        }
+
+        let mut app = Self::with_system(&cc.egui_ctx, system, system_file, settings, profile, lock, speech_engine);
+        app.launch_system_file = args.system;
+        app.lam = Lam::load(storage);
+        for path in &args.translations {
+            match Catalog::load_file(path) {
+                Ok(catalog) => app.catalogs.push(catalog),
+                Err(err) => warn!("!!! Failed to load translations: {}", err),
+            }
+        }
+        app.catalog = i18n::find(&app.settings.locale, &app.catalogs);
+        // Whoever locked it is who it's locked for.
+        app.picking_user = users.pick_at_launch && users.users.len() > 1 && !app.lock.locked;
+        app.users = users;
+        app
+    }
+
+    /// An app showing `system`, without a window, e.g. for rendering boards to images.
+    pub fn headless(ctx: &egui::Context, system: System, settings: Settings) -> Self {
+        Self::with_system(ctx, system, None, settings, Profile::default(), Lock::default(), SpeechEngine::silent())
+    }
+
+    fn with_system(ctx: &egui::Context, system: System, system_file: Option<String>, settings: Settings, profile: Profile, lock: Lock, speech_engine: SpeechEngine) -> Self {
+        let panel = Panel::default();
+        let editing_visibility = false;
+        let launch_system_file = None;
+
+        let theme = theme::find(&settings.theme, &system.themes).unwrap_or_else(|| {
+            warn!("No theme named '{}'; using the default.", settings.theme);
+            Theme::default()
+        });
+        theme.apply(ctx);
+        let restyle = false;
+
+        let search_index = SearchIndex::new(&system);
+        info!("Indexed {} words for searching.", search_index.len());
+
+        let view = View::Board;
+        let nav = Navigation::new(0);
         let current_hotbar_page = 0;
+        let highlight = None;
+        let modeling = None;
+        let search_query = String::new();
+        let focus_search = false;
+        let keyboard_text = String::new();
+        let drawer_open = false;
+        let drawer_swipe = 0.0;
+        let editor = None;
+        let pin_entry = String::new();
+        let hold_started = None;
+        let unlocking = false;
+        #[cfg(not(target_arch = "wasm32"))]
+        let decorations_locked = None;
+        let loader = Loader::new(ctx);
+        // The stored or bundled System is shown until this one loads, or if it can't be.
+        #[cfg(target_arch = "wasm32")]
+        if let Some(url) = crate::loader::query_url() {
+            loader.fetch(&url);
+        }
+        let system_error = None;
+        let store_system = None;
+        let events = vec![];
+        let listeners = vec![];
+        let shown_folder = None;
+        let ctx = ctx.clone();
+        let apply_update = None;
+        let update_dismissed = false;
+        let users = Users::default();
+        let picking_user = false;
+        let switch_user = None;
+        let new_user_name = String::new();
+        let lam = Lam::default();
+        let redact_word = String::new();
+        let lam_status = None;
+        let catalogs = i18n::bundled();
+        let catalog = i18n::find(&settings.locale, &catalogs);
+
+        Self {
+            speech_engine, panel, system, settings, profile, editing_visibility, theme, restyle, view,
+            nav, current_hotbar_page, highlight, modeling,
+            search_index, search_query, focus_search, keyboard_text,
+            drawer_open, drawer_swipe, system_file, launch_system_file, editor, pin_entry, hold_started,
+            lock, unlocking, loader, system_error, store_system,
+            #[cfg(not(target_arch = "wasm32"))]
+            decorations_locked,
+            events, listeners, shown_folder, ctx, apply_update, update_dismissed,
+            users, picking_user, switch_user, new_user_name, lam, redact_word, lam_status,
+            catalogs, catalog,
+        }
+    }
+
+    /// Something that can load Systems in the background, which the app will switch to.
+    pub fn loader(&self) -> Loader {
+        self.loader.clone()
+    }
+
+    /// Switches to the System in `contents`, from `source`, and keeps it for next time.
+    /// If it can't be used, the current System stays.
+    pub fn load_system(&mut self, source: &str, contents: &str) -> Result<(), String> {
+        let system = System::load_checked(contents)?;
+        info!("Loaded System from {}.", source);
+        self.store_system = Some(system.serialize_json());
+        self.replace_system(system);
+        Ok(())
+    }
+
+    /// Calls `listener` with everything that happens from now on, at the end of each frame.
+    pub fn on_event(&mut self, listener: Listener) {
+        self.listeners.push(listener);
+    }
+
+    pub fn panel_text(&self) -> String {
+        self.panel.get_text()
+    }
+
+    /// Replaces what's in the panel with `text`, one entry per word.
+    pub fn set_panel_text(&mut self, text: &str) {
+        self.panel.clear();
+        for word in text.split_whitespace() {
+            self.panel.add_entry(&Button::new(word.to_string(), None, None));
+        }
+        self.ctx.request_repaint();
+    }
+
+    /// Opens the folder with id `folder`, as if its button was pressed. Returns false
+    /// if there's no such folder, or the lock blocks it.
+    pub fn open_folder(&mut self, folder: &str) -> bool {
+        let Some(index) = self.system.folder_index(folder) else { return false };
+        if self.lock.blocks(folder) {
+            return false;
+        }
+        self.view = View::Board;
+        self.modeling = None;
+        self.highlight = None;
+        self.nav.open(index);
+        self.ctx.request_repaint();
+        true
+    }
+
+    /// Lets the user update to a newer version: `apply` is called if they choose to.
+    pub fn offer_update(&mut self, apply: Box<dyn FnOnce()>) {
+        self.apply_update = Some(apply);
+        self.update_dismissed = false;
+        self.ctx.request_repaint();
+    }
+
+    fn update_now(&mut self) {
+        if let Some(apply) = self.apply_update.take() {
+            info!("Updating to the new version.");
+            apply();
+        }
+    }
+
+    /// Saves everything for the current user, then loads everything for user `index`.
+    fn change_user(&mut self, index: usize, storage: &mut dyn eframe::Storage) {
+        eframe::App::save(self, storage);
+        self.users.current = index;
+        self.users.save(storage);
+        info!("Switched to user {}.", self.users.current().name);
+
+        let user_storage = UserStorage::new(&*storage, self.users.current());
+        let storage = Some(&user_storage as &dyn eframe::Storage);
+        self.settings = Settings::load(storage);
+        self.catalog = i18n::find(&self.settings.locale, &self.catalogs);
+        apply_speech_settings(&mut self.speech_engine, &self.settings);
+        self.profile = Profile::load(storage);
+        self.lock = Lock::load(storage);
+        self.lam = Lam::load(storage);
+        self.lam_status = None;
+        self.editing_visibility = false;
+        self.unlocking = false;
+        self.pin_entry.clear();
+
+        // Nothing one person was saying, or looking for, is left for the next.
+        // (replace_system clears the sentence, and where they were in the System.)
+        self.speech_engine.stop();
+        self.search_query.clear();
+        self.keyboard_text.clear();
+        self.drawer_open = false;
+        self.redact_word.clear();
+
+        match user_system(storage, self.launch_system_file.as_ref()) {
+            Ok((system, system_file)) => {
+                self.replace_system(system);
+                self.system_file = system_file;
+            },
+            Err(err) => {
+                warn!("!!! {}", err);
+                self.replace_system(bundled_system());
+            },
+        }
+    }
+
+    fn show_user_picker(&mut self, ctx: &egui::Context) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.heading(self.catalog.t("Who's using SGS?"));
+                let size = egui::vec2(ui.available_width().min(400.0), 60.0);
+                for (index, user) in self.users.users.iter().enumerate() {
+                    if ui.add_sized(size, egui::Button::new(egui::RichText::new(&user.name).size(24.0))).clicked() {
+                        if index != self.users.current {
+                            self.switch_user = Some(index);
+                        }
+                        self.picking_user = false;
+                    }
+                }
+            });
+        });
+    }
+
+    /// Redraws soon, e.g. after performing an action from outside the app.
+    pub fn request_repaint(&self) {
+        self.ctx.request_repaint();
+    }
+
+    /// Goes back to the bundled System, forgetting any imported one.
+    pub fn use_bundled_system(&mut self) {
+        self.store_system = Some(String::new());
+        self.replace_system(bundled_system());
+    }
+
+    fn replace_system(&mut self, system: System) {
+        self.search_index = SearchIndex::new(&system);
+        self.system = system;
+        self.system_file = None;
+        self.system_error = None;
+        // Everything that points into the old System.
+        self.panel = Panel::default();
+        self.nav = Navigation::new(0);
+        self.current_hotbar_page = 0;
+        self.highlight = None;
+        self.modeling = None;
+        self.editor = None;
+        self.view = View::Board;
 
-        Self { speech_engine, panel, system, current_folder, current_page, current_hotbar_page }
+        self.theme = theme::find(&self.settings.theme, &self.system.themes).unwrap_or_default();
+        self.restyle = true;
+    }
+
+    /// Shows `page` of `folder`, without going through the folders in between.
+    pub fn show_page(&mut self, folder: usize, page: usize) {
+        self.view = View::Board;
+        self.nav.folder = folder;
+        self.nav.page = page;
+    }
+
+    pub fn perform(&mut self, action: &Action) {
+        self.perform_nested(action, 0);
+    }
+
+    fn perform_nested(&mut self, action: &Action, depth: usize) {
+        if self.lock.locked && matches!(action, Action::ToggleSetting(_) | Action::SetVoice(_) | Action::SetTheme(_)) {
+            warn!("Ignoring '{}' while locked.", action);
+            return;
+        }
+
+        match action {
+            Action::Speak => {
+                speak(&mut self.speech_engine, &mut self.events, self.panel.get_pronouncible_text(&self.system));
+                if self.settings.clear_after_speaking {
+                    self.panel.clear();
+                }
+            },
+            Action::Clear => self.panel.clear(),
+            Action::DeleteWord => self.panel.remove_last_entry(),
+            Action::Back | Action::Home => {
+                self.modeling = None;
+                self.highlight = None;
+                self.view = View::Board;
+                if *action == Action::Back {
+                    self.nav.back();
+                } else {
+                    self.nav.home();
+                }
+                if self.lock.blocks(&self.system.folders[self.nav.folder].id) {
+                    self.nav.home();
+                }
+            },
+            Action::OpenKeyboard => {
+                self.modeling = None;
+                self.view = View::Keyboard;
+            },
+            Action::ToggleSetting(name) => {
+                if !self.settings.toggle(name) {
+                    warn!("!!! Tried to toggle setting named '{}', which does not exist.", name);
+                }
+            },
+            Action::Say(phrase) => speak(&mut self.speech_engine, &mut self.events, phrase.clone()),
+            Action::PauseLogging => {
+                self.lam.paused = !self.lam.paused;
+                info!("Activity logging {}.", if self.lam.paused { "paused" } else { "resumed" });
+            },
+            Action::SetVoice(voice) => {
+                match self.speech_engine.set_voice(voice) {
+                    Ok(()) => self.settings.voice = Some(voice.clone()),
+                    Err(err) => warn!("Failed to change voice: {}", err),
+                }
+            },
+            Action::SetTheme(name) => {
+                if let Some(theme) = theme::find(name, &self.system.themes) {
+                    self.settings.theme = theme.name.clone();
+                    self.theme = theme;
+                    self.restyle = true;
+                } else {
+                    warn!("!!! Tried to use theme named '{}', which does not exist.", name);
+                }
+            },
+            Action::RunMacro(name) => {
+                if depth >= MAX_MACRO_DEPTH {
+                    warn!("!!! Macro '{}' nested too deeply; stopping.", name);
+                    return;
+                }
+
+                let Some(steps) = self.system.macros.get(name).cloned() else {
+                    warn!("!!! Tried to run macro named '{}', which does not exist.", name);
+                    return;
+                };
+
+                for step in steps {
+                    if let Some(action) = Action::parse(&step) {
+                        self.perform_nested(&action, depth + 1);
+                    } else {
+                        warn!("!!! Macro '{}' has an invalid action: '{}'", name, step);
+                    }
+                }
+            },
+        }
     }
 }
 
-impl eframe::App for App {
-    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
-        let screen_size = ctx.input(|i| i.screen_rect().max);
-        let hotbar = &self.system.hotbar;
-        let folder = &self.system.folders[self.current_folder];
-        let dimensions = Dimensions::new(screen_size, folder.rows, folder.cols);
+fn take_typed_word(text: &mut String) -> Option<Button> {
+    let word = text.trim().to_string();
+    text.clear();
 
-        let inner_margin = egui::style::Margin::same(dimensions.margin);
-        ctx.style_mut(|style| {
-            style.spacing.item_spacing = egui::vec2(dimensions.item_spacing, dimensions.item_spacing);
+    if word.is_empty() {
+        None
+    } else {
+        Some(Button::new(word, None, None))
+    }
+}
+
+/// Columns `0..cols` in the order they're laid out: right to left for RTL languages.
+fn reading_order(cols: usize, rtl: bool) -> Vec<usize> {
+    if rtl {
+        (0..cols).rev().collect()
+    } else {
+        (0..cols).collect()
+    }
+}
+
+// Actions from button presses are collected while drawing, and performed afterwards.
+fn queue_action(button: &Button, actions: &mut Vec<Action>) {
+    if let Some(action) = button.get_action() {
+        actions.push(action);
+    } else {
+        warn!("!!! Button '{}' has an invalid action: {:?}", button.label, button.action);
+    }
+}
+
+// Drawing. Each region of the screen gets its own method; `Layout` decides where they go.
+impl App {
+    /// The layout this user chose, or the System's.
+    fn chosen_layout(&self) -> Layout {
+        self.settings.layout.clone().or_else(|| self.system.layout.clone()).unwrap_or_default()
+    }
+
+    /// The layout as shown, mirrored (again) for right-to-left languages.
+    fn layout(&self) -> Layout {
+        let mut layout = self.chosen_layout();
+        layout.mirrored ^= self.catalog.rtl;
+        layout
+    }
+
+    fn show_region(&mut self, ui: &mut egui::Ui, region: Region, layout: &Layout, dimensions: &Dimensions, actions: &mut Vec<Action>) {
+        match region {
+            Region::Speak => self.show_speak(ui, dimensions, actions),
+            Region::Panel => self.show_panel(ui, dimensions, actions),
+            Region::Delete => self.show_delete(ui, dimensions, actions),
+            Region::Clear => self.show_clear(ui, dimensions, actions),
+            Region::Selector => self.show_selector(ui, layout, dimensions),
+            Region::Board => self.show_board(ui, layout, dimensions, actions),
+            Region::Related => self.show_related(ui, layout, dimensions),
+            Region::Variants => self.show_variants(ui, layout, dimensions),
+        }
+    }
+
+    // Everything stacked: selector strip, then the panel row, then the board.
+    // Related words and variants live in the drawer.
+    fn show_portrait(&mut self, ui: &mut egui::Ui, layout: &Layout, dimensions: &Dimensions, actions: &mut Vec<Action>) {
+        if layout.shows(Region::Selector) {
+            self.show_selector(ui, layout, dimensions);
+        }
+
+        egui::Grid::new("portrait-top").show(ui, |ui| {
+            for region in layout.portrait_top_row() {
+                self.show_region(ui, region, layout, dimensions, actions);
+            }
         });
-        let frame = egui::containers::Frame::central_panel(&ctx.style()).inner_margin(inner_margin);
 
-        egui::CentralPanel::default().frame(frame).show(ctx, |ui| {
-            egui::Grid::new("main-grid").show(ui, |ui| {
-                // With each [] being a sub-grid:
-                // | [Speak]           | [Panel] | [Delete, Clear] |
-                // -------------------------------------------------
-                // | [Folder selector] | [system] | [extra]        |
-
-                // Row 1, Column 1
-                egui::Grid::new("top-left").show(ui, |ui| {
-                    if self.speech_engine.is_speaking() {
-                        let egui_button = egui::Button::new("Stop");
-                        if ui.add_sized(dimensions.button_size, egui_button).clicked() {
-                            self.speech_engine.stop();
+        self.show_board(ui, layout, dimensions, actions);
+    }
+
+    fn show_drawer(&mut self, ui: &mut egui::Ui, layout: &Layout, dimensions: &Dimensions) {
+        let label = if self.drawer_open { self.catalog.t("▼ Related words") } else { self.catalog.t("▲ Related words") };
+        let handle_size = [ui.available_width(), dimensions.button_size[1] * layout::DRAWER_HANDLE_HEIGHT];
+        let response = ui.add_sized(handle_size, egui::Button::new(label).sense(egui::Sense::click_and_drag()));
+
+        // Tap the handle, or swipe it up or down.
+        if response.clicked() {
+            self.drawer_open = !self.drawer_open;
+        }
+        if response.dragged() {
+            self.drawer_swipe += response.drag_delta().y;
+        }
+        if response.drag_released() {
+            if self.drawer_swipe < -DRAWER_SWIPE {
+                self.drawer_open = true;
+            } else if self.drawer_swipe > DRAWER_SWIPE {
+                self.drawer_open = false;
+            }
+            self.drawer_swipe = 0.0;
+        }
+
+        // Modeling may need a tap in here.
+        if self.modeling.as_ref().is_some_and(|m| matches!(m.current_step(), Some(Step::Related { .. } | Step::Variant { .. }))) {
+            self.drawer_open = true;
+        }
+
+        if self.drawer_open {
+            ui.horizontal(|ui| {
+                if layout.shows(Region::Related) {
+                    self.show_related(ui, layout, dimensions);
+                }
+                if layout.shows(Region::Variants) {
+                    self.show_variants(ui, layout, dimensions);
+                }
+            });
+        }
+    }
+
+    fn show_speak(&mut self, ui: &mut egui::Ui, dimensions: &Dimensions, actions: &mut Vec<Action>) {
+        egui::Grid::new("top-left").show(ui, |ui| {
+            if self.speech_engine.is_speaking() {
+                let egui_button = egui::Button::new(self.catalog.t("Stop"));
+                if ui.add_sized(dimensions.top_size, egui_button).clicked() {
+                    self.speech_engine.stop();
+                }
+            } else {
+                let egui_button = egui::Button::new(self.catalog.t("Speak"));
+                if ui.add_sized(dimensions.top_size, egui_button).clicked() {
+                    actions.push(Action::Speak);
+                }
+            }
+        });
+    }
+
+    fn show_panel(&mut self, ui: &mut egui::Ui, dimensions: &Dimensions, actions: &mut Vec<Action>) {
+        egui::Grid::new("top-center").show(ui, |ui| {
+            let inner_spacing = ui.ctx().style().spacing.item_spacing[0];
+            let cols = (dimensions.panel_width / (dimensions.button_size[0] + inner_spacing)) as usize;
+
+            ui.vertical(|ui| {
+                ui.set_max_width(dimensions.panel_width);
+
+                ui.horizontal(|ui| {
+                    let nav_size = [dimensions.button_size[0] / 2.0, BREADCRUMB_HEIGHT - dimensions.item_spacing];
+
+                    let egui_button = egui::Button::new(self.catalog.t("Back"));
+                    if ui.add_enabled_ui(self.nav.can_go_back(), |ui| ui.add_sized(nav_size, egui_button)).inner.clicked() {
+                        actions.push(Action::Back);
+                    }
+
+                    let egui_button = egui::Button::new(self.catalog.t("Home"));
+                    if ui.add_sized(nav_size, egui_button).clicked() {
+                        actions.push(Action::Home);
+                    }
+
+                    self.show_page_button(ui, nav_size);
+                    self.show_lock_button(ui, nav_size);
+
+                    // e.g. Nouns › Animals › Body Parts
+                    for (idx, (id, name)) in self.system.breadcrumb(self.nav.folder).into_iter().enumerate() {
+                        if idx > 0 {
+                            ui.label("›");
                         }
-                    } else {
-                        let egui_button = egui::Button::new("Speak");
-                        if ui.add_sized(dimensions.button_size, egui_button).clicked() {
-                            self.speech_engine.speak(self.panel.get_pronouncible_text(&self.system)).unwrap();
-                            self.panel.clear();
+
+                        if ui.add(egui::Button::new(i18n::display(&name)).frame(false)).clicked() {
+                            if let Some(folder) = self.system.folder_index(&id) {
+                                self.modeling = None;
+                                self.highlight = None;
+                                self.view = View::Board;
+                                self.nav.open(folder);
+                            }
                         }
                     }
                 });
 
-                // Row 1, Column 2
-                egui::Grid::new("top-center").show(ui, |ui| {
-                    let cols = self.system.folders[self.current_folder].cols;
-                    let inner_spacing = ui.ctx().style().spacing.item_spacing[0];
-                    let max_width = (cols as f32) * (dimensions.button_size[0] + inner_spacing);
-
-                    ui.with_layout(egui::Layout::left_to_right(egui::Align::TOP).with_main_wrap(true), |ui| {
-                        ui.set_max_width(max_width);
-
-                        for entry in &self.panel.entries {
-                            // It doesn't make sense to use a button here,
-                            // I just want each phrase to be distinguishable.
-                            //
-                            // For now, assign it to nothing.
-                            // Long term, probably use a label with a background.
-                            let egui_button = egui::Button::new(entry.get_label(&self.system).clone());
-                            if self.panel.entries.len() > cols {
-                                let _ = ui.add(egui_button);
-                            } else {
-                                let _ = ui.add_sized(dimensions.button_size, egui_button);
-                            };
+                let direction = if self.catalog.rtl { egui::Layout::right_to_left(egui::Align::TOP) } else { egui::Layout::left_to_right(egui::Align::TOP) };
+                ui.with_layout(direction.with_main_wrap(true), |ui| {
+                    if self.editing_visibility {
+                        let folder_id = self.system.folders[self.nav.folder].id.clone();
+                        ui.label(self.catalog.t("Choosing which words to show. Tap a word to hide or show it."));
+                        if ui.button(self.catalog.t("Hide all here")).clicked() {
+                            self.profile.set_all(&folder_id, false);
                         }
-                    });
+                        if ui.button(self.catalog.t("Show all here")).clicked() {
+                            self.profile.set_all(&folder_id, true);
+                        }
+                        if ui.button(self.catalog.t("Done")).clicked() {
+                            self.editing_visibility = false;
+                        }
+                        ui.end_row();
+                    }
+
+                    if let Some(modeling) = &self.modeling {
+                        ui.label(self.catalog.fill("Showing how to say \"{}\": step {} of {}.", &[&modeling.word, &(modeling.current + 1).to_string(), &modeling.steps.len().to_string()]));
+                        if ui.button(self.catalog.t("Stop showing")).clicked() {
+                            self.modeling = None;
+                        }
+                        ui.end_row();
+                    }
+
+                    for entry in &self.panel.entries {
+                        // It doesn't make sense to use a button here,
+                        // I just want each phrase to be distinguishable.
+                        //
+                        // For now, assign it to nothing.
+                        // Long term, probably use a label with a background.
+                        let egui_button = egui::Button::new(i18n::display(&entry.get_label(&self.system)));
+                        if self.panel.entries.len() > cols {
+                            let _ = ui.add(egui_button);
+                        } else {
+                            let _ = ui.add_sized(dimensions.top_size, egui_button);
+                        };
+                    }
                 });
+            });
+        });
+    }
 
-                // Row 1, Column 3
-                egui::Grid::new("delete-btn").show(ui, |ui| {
-                    let egui_button = egui::Button::new("Delete");
+    fn show_delete(&mut self, ui: &mut egui::Ui, dimensions: &Dimensions, actions: &mut Vec<Action>) {
+        egui::Grid::new("delete-btn").show(ui, |ui| {
+            let egui_button = egui::Button::new(self.catalog.t("Delete"));
+            if ui.add_sized(dimensions.top_size, egui_button).clicked() {
+                actions.push(Action::DeleteWord);
+            }
+        });
+    }
+
+    fn show_clear(&mut self, ui: &mut egui::Ui, dimensions: &Dimensions, actions: &mut Vec<Action>) {
+        egui::Grid::new("clear-btn").show(ui, |ui| {
+            let egui_button = egui::Button::new(self.catalog.t("Clear"));
+            if ui.add_sized(dimensions.top_size, egui_button).clicked() {
+                actions.push(Action::Clear);
+            }
+        });
+    }
+
+    fn show_selector(&mut self, ui: &mut egui::Ui, layout: &Layout, dimensions: &Dimensions) {
+        // In portrait, the selector is a strip across the top instead of a column.
+        let size = if dimensions.portrait { dimensions.button_size } else { dimensions.sized(layout.selector_width) };
+        let end_row = |ui: &mut egui::Ui| {
+            if !dimensions.portrait {
+                ui.end_row();
+            }
+        };
+
+        egui::Grid::new("folder-selector-grid").show(ui, |ui| {
+            let current_toplevel_folder = self.system.toplevel_folder_for(self.nav.folder);
+            for (idx, folder) in self.system.toplevel_folders().iter().enumerate() {
+                let folder_idx = self.system.folder_index(&folder.id).unwrap_or(idx);
+                let step = Step::SelectFolder { folder: folder_idx };
+                let selected = self.view == View::Board && current_toplevel_folder == idx;
+                let mut egui_button = colored(folder.name.clone(), &self.system.folder_colors(folder_idx), &self.theme).selected(selected);
+                if selected {
+                    // A colored fill hides the usual selected look, so outline it instead.
+                    egui_button = egui_button.stroke(self.theme.selected_stroke(ui.visuals()));
+                }
+                if self.modeling.as_ref().is_some_and(|m| m.is_current(step)) {
+                    egui_button = highlighted(egui_button, ui, &self.theme);
+                }
+                let blocked = self.lock.blocks(&folder.id);
+                if ui.add_enabled_ui(!blocked, |ui| ui.add_sized(size, egui_button)).inner.clicked() {
+                    navigation::advance(&mut self.modeling, step);
+                    self.view = View::Board;
+                    self.highlight = None;
+                    self.nav.open(folder_idx);
+                }
+                end_row(ui);
+            }
+
+            let egui_button = egui::Button::new(self.catalog.t("Search")).selected(self.view == View::Search);
+            if ui.add_sized(size, egui_button).clicked() {
+                self.view = View::Search;
+                self.focus_search = true;
+            }
+            end_row(ui);
+
+            if !self.lock.locked {
+                let egui_button = egui::Button::new(self.catalog.t("Settings")).selected(self.view == View::Settings);
+                if ui.add_sized(size, egui_button).clicked() {
+                    self.modeling = None;
+                    self.view = View::Settings;
+                }
+                end_row(ui);
+            }
+        });
+    }
+
+    fn show_board(&mut self, ui: &mut egui::Ui, layout: &Layout, dimensions: &Dimensions, actions: &mut Vec<Action>) {
+        match self.view {
+            View::Search => self.show_search(ui, dimensions),
+            View::Keyboard => self.show_keyboard(ui, dimensions),
+            View::Settings => self.show_settings(ui, dimensions, actions),
+            View::Board => {
+                egui::Grid::new("active-folder").show(ui, |ui| {
+                    let hotbar = layout.hotbar_position();
+                    if hotbar == HotbarPosition::Top {
+                        self.show_hotbar(ui, dimensions, actions);
+                    }
+                    self.show_folder(ui, dimensions, actions);
+                    if hotbar == HotbarPosition::Bottom {
+                        self.show_hotbar(ui, dimensions, actions);
+                    }
+                });
+            },
+        }
+    }
+
+    fn show_search(&mut self, ui: &mut egui::Ui, dimensions: &Dimensions) {
+        let folder = &self.system.folders[self.nav.folder];
+
+        ui.vertical(|ui| {
+            let width = (folder.cols as f32) * (dimensions.button_size[0] + dimensions.item_spacing) - dimensions.item_spacing;
+            let text_edit = egui::TextEdit::singleline(&mut self.search_query).hint_text(self.catalog.t("Search for a word"));
+            let response = ui.add_sized([width, dimensions.button_size[1]], text_edit);
+            if self.focus_search {
+                response.request_focus();
+                self.focus_search = false;
+            }
+
+            // Each result gets three cells: the word itself, where to find it, and how to get there.
+            let per_row = cmp::max(1, folder.cols / 3);
+            let (profile, system, lock) = (&self.profile, &self.system, &self.lock);
+            let results: Vec<_> = self.search_index.search(&self.search_query).into_iter()
+                .filter(|r| system.folders.get(r.folder).is_some_and(|f| !lock.blocks(&f.id)))
+                .filter(|r| system.folders[r.folder].buttons.get(r.index).and_then(|b| b.as_ref()).is_some_and(|b| profile.shows(system, r.folder, b)))
+                .collect();
+
+            egui::Grid::new("search-results").show(ui, |ui| {
+                for (idx, result) in results.iter().take(per_row * folder.rows).enumerate() {
+                    if idx > 0 && idx % per_row == 0 {
+                        ui.end_row();
+                    }
+
+                    let egui_button = egui::Button::new(result.label.clone());
                     if ui.add_sized(dimensions.button_size, egui_button).clicked() {
-                        self.panel.remove_last_entry();
+                        if let Some(button) = result.button(&self.system) {
+                            let result_folder = &self.system.folders[result.folder];
+                            let (page, _, _) = result_folder.position_of(result.index);
+                            self.lam.press(Source::Search, Some(&result_folder.id), Some(page), Some(result.index), &button.label);
+                            self.panel.add_entry(&button);
+                        }
+                    }
+
+                    let result_folder = &self.system.folders[result.folder];
+                    let (page, _, _) = result_folder.position_of(result.index);
+                    let label = self.catalog.fill("Show me\n{} {}/{}", &[&result_folder.name, &(page + 1).to_string(), &result_folder.pages().to_string()]);
+                    let egui_button = egui::Button::new(label);
+                    if ui.add_sized(dimensions.button_size, egui_button).clicked() {
+                        self.view = View::Board;
+                        self.nav.open(result.folder);
+                        self.nav.page = page;
+                        self.highlight = Some((result.folder, result.index));
+                    }
+
+                    let egui_button = egui::Button::new(self.catalog.t("Show taps"));
+                    if ui.add_sized(dimensions.button_size, egui_button).clicked() {
+                        let visible = |folder, button: &Button| {
+                            self.profile.shows(&self.system, folder, button)
+                                && !button.folder.as_ref().is_some_and(|id| self.lock.blocks(id))
+                        };
+                        if let Some(mut steps) = navigation::path_to_visible(&self.system, result.folder, result.index, &visible) {
+                            if let Some(index) = result.related {
+                                steps.push(Step::Related { index });
+                            }
+                            if let Some(index) = result.variant {
+                                steps.push(Step::Variant { index });
+                            }
+                            self.modeling = Some(Modeling::new(result.label.clone(), steps));
+                            self.view = View::Board;
+                            self.highlight = None;
+                        } else {
+                            warn!("No way to reach '{}' from the folder selector.", result.label);
+                        }
+                    }
+                }
+                ui.end_row();
+            });
+
+            if results.len() > per_row * folder.rows {
+                ui.label(self.catalog.fill("Showing {} of {} matches.", &[&(per_row * folder.rows).to_string(), &results.len().to_string()]));
+            }
+        });
+    }
+
+    fn show_keyboard(&mut self, ui: &mut egui::Ui, dimensions: &Dimensions) {
+        let folder = &self.system.folders[self.nav.folder];
+
+        ui.vertical(|ui| {
+            let width = (folder.cols as f32) * (dimensions.button_size[0] + dimensions.item_spacing) - dimensions.item_spacing;
+            let key_width = (width - 9.0 * dimensions.item_spacing) / 10.0;
+            let key_size = [key_width, dimensions.button_size[1]];
+
+            ui.add_sized([width, dimensions.button_size[1]], egui::Label::new(egui::RichText::new(&self.keyboard_text).heading()));
+
+            for keys in KEYBOARD_ROWS {
+                ui.horizontal(|ui| {
+                    for key in keys.chars() {
+                        if ui.add_sized(key_size, egui::Button::new(key.to_string())).clicked() {
+                            self.keyboard_text.push(key);
+                        }
                     }
                 });
+            }
+
+            ui.horizontal(|ui| {
+                if ui.add_sized([key_width * 2.0, key_size[1]], egui::Button::new("⌫")).clicked() {
+                    self.keyboard_text.pop();
+                }
+
+                // Adds what's been typed to the panel, as a single entry.
+                let space_width = key_width * 6.0 + dimensions.item_spacing * 5.0;
+                if ui.add_sized([space_width, key_size[1]], egui::Button::new(self.catalog.t("Space"))).clicked() {
+                    if let Some(button) = take_typed_word(&mut self.keyboard_text) {
+                        self.lam.press(Source::Keyboard, None, None, None, &button.label);
+                        self.panel.add_entry(&button);
+                    }
+                }
 
-                egui::Grid::new("clear-btn").show(ui, |ui| {
-                    let egui_button = egui::Button::new("Clear");
+                if ui.add_sized([key_width * 2.0, key_size[1]], egui::Button::new(self.catalog.t("Done"))).clicked() {
+                    if let Some(button) = take_typed_word(&mut self.keyboard_text) {
+                        self.lam.press(Source::Keyboard, None, None, None, &button.label);
+                        self.panel.add_entry(&button);
+                    }
+                    self.view = View::Board;
+                }
+            });
+        });
+    }
+
+    fn show_settings(&mut self, ui: &mut egui::Ui, dimensions: &Dimensions, actions: &mut Vec<Action>) {
+        ui.vertical(|ui| {
+            ui.heading(self.catalog.t("Theme"));
+            ui.horizontal_wrapped(|ui| {
+                for theme in theme::all(&self.system.themes) {
+                    let egui_button = egui::Button::new(theme.name.clone()).selected(theme.name == self.theme.name);
                     if ui.add_sized(dimensions.button_size, egui_button).clicked() {
-                        self.panel.clear();
+                        actions.push(Action::SetTheme(theme.name));
+                    }
+                }
+            });
+
+            ui.heading(self.catalog.t("Language"));
+            ui.horizontal_wrapped(|ui| {
+                let mut chosen = None;
+                for catalog in &self.catalogs {
+                    if ui.selectable_label(catalog.locale == self.catalog.locale, i18n::display(&catalog.name)).clicked() {
+                        chosen = Some(catalog.locale.clone());
+                    }
+                }
+                if let Some(locale) = chosen {
+                    self.catalog = i18n::find(&locale, &self.catalogs);
+                    self.settings.locale = locale;
+                }
+            });
+
+            ui.heading(self.catalog.t("System"));
+            ui.label(self.catalog.fill("Using {}.", &[&self.system.name]));
+            if self.apply_update.is_some() && ui.button(self.catalog.t("Update SGS to the new version")).clicked() {
+                self.update_now();
+            }
+            #[cfg(target_arch = "wasm32")]
+            ui.horizontal_wrapped(|ui| {
+                if ui.button(self.catalog.t("Open a System file")).clicked() {
+                    self.loader.pick_file();
+                }
+                if ui.button(self.catalog.t("Use the built-in System")).clicked() {
+                    self.use_bundled_system();
+                }
+            });
+
+            ui.heading(self.catalog.t("Users"));
+            ui.label(self.catalog.fill("Using SGS as {}. Each user has their own System, settings, words and lock.", &[&self.users.current().name]));
+            ui.horizontal_wrapped(|ui| {
+                for (index, user) in self.users.users.iter().enumerate() {
+                    if ui.selectable_label(index == self.users.current, &user.name).clicked() && index != self.users.current {
+                        self.switch_user = Some(index);
+                    }
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut self.new_user_name).hint_text(self.catalog.t("Name")));
+                if ui.button(self.catalog.t("Add user")).clicked() {
+                    match self.users.add(&self.new_user_name) {
+                        Ok(()) => self.new_user_name.clear(),
+                        Err(err) => warn!("Couldn't add a user: {}", err),
+                    }
+                }
+            });
+            let current = self.users.current;
+            if self.users.users.len() > 1 {
+                egui::CollapsingHeader::new(self.catalog.t("Remove a user")).show(ui, |ui| {
+                    // Not the first user, who owns what was saved before there were others,
+                    // nor the current one, whose things are loaded.
+                    let mut removed = None;
+                    for (index, user) in self.users.users.iter().enumerate().skip(1).filter(|(index, _)| *index != current) {
+                        if ui.button(self.catalog.fill("Remove {}", &[&user.name])).clicked() {
+                            removed = Some(index);
+                        }
+                    }
+                    if let Some(index) = removed {
+                        self.users.remove(index);
                     }
                 });
+            }
+            ui.checkbox(&mut self.users.pick_at_launch, self.catalog.t("Ask who's using SGS when it starts"));
 
-                ui.end_row();
+            ui.heading(self.catalog.t("Words"));
+            if ui.button(self.catalog.t("Choose which words to show")).clicked() {
+                self.editing_visibility = true;
+                self.modeling = None;
+                self.view = View::Board;
+            }
 
-                // Row 2, Column 1
-                egui::Grid::new("folder-selector-grid").show(ui, |ui| {
-                    let current_toplevel_folder = self.system.toplevel_folder_for(self.current_folder);
-                    for (idx, folder) in self.system.toplevel_folders().iter().enumerate() {
-                        let egui_button = egui::Button::new(folder.name.clone()).selected(current_toplevel_folder == idx);
-                        if ui.add_sized(dimensions.button_size, egui_button).clicked() {
-                            self.current_page = 0;
-                            self.current_folder = idx;
+            ui.heading(self.catalog.t("Editing"));
+            if let Some(pin) = self.settings.pin.clone() {
+                ui.horizontal(|ui| {
+                    ui.add(egui::TextEdit::singleline(&mut self.pin_entry).password(true).hint_text(self.catalog.t("PIN")));
+                    if ui.button(self.catalog.t("Edit this System")).clicked() {
+                        if self.pin_entry == pin {
+                            self.open_editor();
+                        } else {
+                            warn!("Wrong PIN entered for the editor.");
                         }
-                        ui.end_row();
+                        self.pin_entry.clear();
                     }
                 });
+            } else {
+                // Has to be held down, so it doesn't get opened by accident.
+                let response = ui.add(egui::Button::new(self.catalog.t("Hold to edit this System")).sense(egui::Sense::drag()));
+                if held(&mut self.hold_started, ui, &response, EDIT_HOLD_SECONDS) {
+                    self.open_editor();
+                }
+            }
 
-                // Row 2, Column 2
-                egui::Grid::new("active-folder").show(ui, |ui| {
-                    for row in 0..folder.rows {
-                        for col in 0..folder.cols {
-                            if col == (folder.cols - 1) && row == (folder.rows - 1) && folder.needs_pagination() {
-                                let num_buttons = folder.buttons.len();
-                                let pages = num_buttons / (folder.rows * folder.cols);
-
-                                // Account for the last page being only partially filled.
-                                let pages =
-                                    if (pages * ((folder.rows * folder.cols) - 1)) < num_buttons {
-                                        pages + 1
-                                    } else {
-                                        pages
-                                    };
-
-                                let label = format!("{}/{}\n->", self.current_page + 1, pages);
-                                let egui_button = egui::Button::new(label);
-                                if ui.add_sized(dimensions.button_size, egui_button).clicked() {
-                                    self.current_page = folder.next_page(self.current_page);
-                                }
-                                break;
-                            }
+            ui.heading(self.catalog.t("Lock"));
+            ui.label(self.catalog.t("Locking turns off settings, editing and closing the app, until a caregiver unlocks it."));
+            if ui.button(self.catalog.t("Lock now")).clicked() {
+                self.lock_now();
+            }
+            egui::CollapsingHeader::new(self.catalog.t("Folders to block while locked")).show(ui, |ui| {
+                for folder in &self.system.folders {
+                    let mut blocked = self.lock.blocked_folders.contains(&folder.id);
+                    if ui.checkbox(&mut blocked, &folder.id).changed() {
+                        self.lock.set_blocked(&folder.id, blocked);
+                    }
+                }
+            });
 
+            ui.heading(self.catalog.t("Activity log"));
+            ui.label(self.catalog.t("Logs each button press and everything spoken, for a speech-language pathologist. It's kept on this device."));
+            ui.checkbox(&mut self.lam.enabled, self.catalog.t("Log activity"));
+            if self.lam.enabled {
+                ui.checkbox(&mut self.lam.paused, self.catalog.t("Pause logging"));
+            }
+            ui.label(self.catalog.fill("{} entries logged.", &[&self.lam.records.len().to_string()]));
+            ui.horizontal_wrapped(|ui| {
+                let exports = [("Export as LAM", "sgs-activity.lam", Lam::to_lam as fn(&Lam) -> String), ("Export as CSV", "sgs-activity.csv", Lam::to_csv)];
+                for (label, file_name, format) in exports {
+                    if ui.button(self.catalog.t(label)).clicked() {
+                        self.lam_status = Some(match lam::export(file_name, &format(&self.lam)) {
+                            Ok(place) => self.catalog.fill("Saved to {}.", &[&place]),
+                            Err(err) => self.catalog.fill("Couldn't export the log: {}", &[&err]),
+                        });
+                    }
+                }
+                if ui.button(self.catalog.t("Clear log")).clicked() {
+                    self.lam.clear();
+                }
+            });
+            ui.horizontal(|ui| {
+                ui.add(egui::TextEdit::singleline(&mut self.redact_word).hint_text(self.catalog.t("Word or name")));
+                if ui.button(self.catalog.t("Redact")).clicked() {
+                    let changed = self.lam.redact(&self.redact_word);
+                    self.lam_status = Some(self.catalog.fill("Redacted {} entries.", &[&changed.to_string()]));
+                    self.redact_word.clear();
+                }
+            });
+            if let Some(status) = &self.lam_status {
+                ui.label(status);
+            }
 
-                            if let Some(button) = folder.get_button(self.current_page, col, row) {
-                                let egui_button = egui::Button::new(button.label.clone());
-                                if ui.add_sized(dimensions.button_size, egui_button).clicked() {
-                                    if let Some(btn_folder) = &button.folder {
-                                        let index = self.system.folders.iter().position(|x| &x.id == btn_folder);
-                                        if let Some(index) = index {
-                                            self.current_folder = index;
-                                        } else {
-                                            warn!("!!! Tried to open folder named '{}', which does not exist.", btn_folder);
-                                        }
-                                    } else if folder.immediate {
-                                        self.speech_engine.speak(button.get_pronouncible_text(&self.system)).expect("Failed to speak word");
-                                    } else {
-                                        self.panel.add_entry(button);
+            ui.heading(self.catalog.t("Speech"));
+            ui.checkbox(&mut self.settings.speak_each_word, self.catalog.t("Speak each word as it's added"));
+            ui.checkbox(&mut self.settings.clear_after_speaking, self.catalog.t("Clear the panel after speaking"));
+            let voices = self.speech_engine.voices();
+            if voices.is_empty() {
+                // Browsers load voices in the background; this redraws when they're ready.
+                ui.label(self.catalog.t("No voices to choose from (yet)."));
+            }
+            egui::CollapsingHeader::new(self.catalog.t("Voice")).show(ui, |ui| {
+                for voice in voices {
+                    let selected = self.settings.voice.as_ref().is_some_and(|v| v.eq_ignore_ascii_case(&voice));
+                    if ui.selectable_label(selected, &voice).clicked() {
+                        actions.push(Action::SetVoice(voice));
+                    }
+                }
+            });
+            let rate = ui.add(egui::Slider::new(&mut self.settings.rate, 0.5..=2.0).text(self.catalog.t("Speed")));
+            let pitch = ui.add(egui::Slider::new(&mut self.settings.pitch, 0.5..=2.0).text(self.catalog.t("Pitch")));
+            if rate.changed() || pitch.changed() {
+                if let Err(err) = self.speech_engine.set_rate(self.settings.rate).and_then(|_| self.speech_engine.set_pitch(self.settings.pitch)) {
+                    warn!("Failed to change speaking rate or pitch: {}", err);
+                }
+            }
+
+            // Changes here are saved for this user, on top of the System's layout.
+            ui.heading(self.catalog.t("Layout"));
+            let layout = &self.chosen_layout();
+            let mut changed = layout.clone();
+            ui.checkbox(&mut changed.mirrored, self.catalog.t("Mirror the screen (left-handed)"));
+            ui.horizontal(|ui| {
+                ui.label(self.catalog.t("Hotbar:"));
+                for position in ["top", "bottom", "hidden"] {
+                    if ui.selectable_label(changed.hotbar == position, self.catalog.t(position)).clicked() {
+                        changed.hotbar = position.to_string();
+                    }
+                }
+            });
+            for (region, label) in [("related", "Show related words"), ("variants", "Show word variants")] {
+                let mut shown = changed.columns.iter().any(|c| c == region);
+                if ui.checkbox(&mut shown, self.catalog.t(label)).changed() {
+                    if shown {
+                        changed.columns.push(region.to_string());
+                    } else {
+                        changed.columns.retain(|c| c != region);
+                    }
+                }
+            }
+            if changed != *layout {
+                self.settings.layout = Some(changed);
+            }
+            if self.settings.layout.is_some() && ui.button(self.catalog.t("Use the System's layout")).clicked() {
+                self.settings.layout = None;
+            }
+        });
+    }
+
+    fn show_folder(&mut self, ui: &mut egui::Ui, dimensions: &Dimensions, actions: &mut Vec<Action>) {
+        let folder = &self.system.folders[self.nav.folder];
+
+        for row in 0..folder.rows {
+            for col in reading_order(folder.cols, self.catalog.rtl) {
+                let index = folder.index_of(self.nav.page, col, row);
+                if let Some(editor) = &mut self.editor {
+                    // Every cell, even empty or hidden ones, can be selected or dragged.
+                    let button = folder.buttons.get(index).and_then(|b| b.as_ref());
+                    let mut egui_button =
+                        if let Some(button) = button {
+                            colored(button.label.clone(), &self.system.button_colors(Some(self.nav.folder), button), &self.theme)
+                        } else {
+                            egui::Button::new(egui::RichText::new("+").weak())
+                        };
+                    if editor.selected == Some(index) {
+                        egui_button = egui_button.selected(true).stroke(self.theme.selected_stroke(ui.visuals()));
+                    }
+                    let response = ui.add_sized(dimensions.button_size, egui_button.sense(egui::Sense::click_and_drag()));
+                    editor.cells.push((index, response.rect));
+                    if response.clicked() {
+                        editor.selected = Some(index);
+                    }
+                    if response.drag_started() {
+                        editor.dragging = Some(index);
+                    }
+                    continue;
+                }
+
+                let cell = folder.get_button(self.nav.page, col, row)
+                    .filter(|b| !b.hidden)
+                    .filter(|b| self.editing_visibility || self.profile.is_visible(&folder.id, b.key()));
+
+                if let Some(button) = cell {
+                    if self.editing_visibility {
+                        let visible = self.profile.is_visible(&folder.id, button.key());
+                        let egui_button =
+                            if visible {
+                                colored(button.label.clone(), &self.system.button_colors(Some(self.nav.folder), button), &self.theme)
+                            } else {
+                                egui::Button::new(egui::RichText::new(button.label.clone()).weak().strikethrough())
+                            };
+
+                        // Folder links get split in two, so they can still be opened.
+                        if let Some(btn_folder) = &button.folder {
+                            ui.vertical(|ui| {
+                                let half = [dimensions.button_size[0], (dimensions.button_size[1] - dimensions.item_spacing) / 2.0];
+                                if ui.add_sized(half, egui_button).clicked() {
+                                    if let Some(index) = self.system.folder_index(btn_folder) {
+                                        self.nav.open(index);
                                     }
                                 }
+                                if ui.add_sized(half, egui::Button::new(if visible { self.catalog.t("Hide") } else { self.catalog.t("Show") })).clicked() {
+                                    self.profile.toggle(&folder.id, button.key());
+                                }
+                            });
+                        } else if ui.add_sized(dimensions.button_size, egui_button).clicked() {
+                            self.profile.toggle(&folder.id, button.key());
+                        }
+                        continue;
+                    }
+
+                    let step =
+                        if button.folder.is_some() {
+                            Step::OpenFolder { folder: self.nav.folder, index }
+                        } else {
+                            Step::Press { folder: self.nav.folder, index }
+                        };
+                    let mut egui_button = colored(button.label.clone(), &self.system.button_colors(Some(self.nav.folder), button), &self.theme);
+                    if self.highlight == Some((self.nav.folder, index)) || self.modeling.as_ref().is_some_and(|m| m.is_current(step)) {
+                        egui_button = highlighted(egui_button, ui, &self.theme);
+                    }
+                    let blocked = button.folder.as_ref().is_some_and(|id| self.lock.blocks(id));
+                    if ui.add_enabled_ui(!blocked, |ui| ui.add_sized(dimensions.button_size, egui_button)).inner.clicked() {
+                        navigation::advance(&mut self.modeling, step);
+                        self.highlight = None;
+                        self.lam.press(Source::Board, Some(&folder.id), Some(self.nav.page), Some(index), &button.label);
+                        if button.folder.is_none() {
+                            self.events.push(Event::ButtonPressed { label: button.label.clone(), folder: Some(folder.id.clone()) });
+                        }
+                        if let Some(btn_folder) = &button.folder {
+                            if let Some(index) = self.system.folder_index(btn_folder) {
+                                self.nav.open(index);
                             } else {
-                                // No button for (row, col).
-                                //let egui_label = egui::Label::new("");
-                                let egui_label = egui::Button::new("");
-                                ui.add_sized(dimensions.button_size, egui_label);
+                                warn!("!!! Tried to open folder named '{}', which does not exist.", btn_folder);
                             }
+                        } else if button.action.is_some() {
+                            queue_action(button, actions);
+                        } else if folder.immediate {
+                            speak(&mut self.speech_engine, &mut self.events, button.get_pronouncible_text(&self.system));
+                        } else {
+                            self.panel.add_entry(button);
                         }
-                        ui.end_row();
                     }
+                } else {
+                    // No button for (row, col), or it's hidden for this user.
+                    //let egui_label = egui::Label::new("");
+                    let egui_label = egui::Button::new("");
+                    ui.add_sized(dimensions.button_size, egui_label);
+                }
+            }
+            ui.end_row();
+        }
+    }
 
-                    for col in 0..folder.cols {
-                        if col == (folder.cols - 1) {
-                            let label = format!("{}\n->", self.current_hotbar_page + 1);
-                            let egui_button = egui::Button::new(label);
-                            if ui.add_sized(dimensions.button_size, egui_button).clicked() {
-                                self.current_hotbar_page = self.system.hotbar.next_page(folder.cols, self.current_hotbar_page);
-                            }
-                            break;
+    fn lock_now(&mut self) {
+        self.lock.locked = true;
+        self.unlocking = false;
+        self.editor = None;
+        self.editing_visibility = false;
+        if self.view == View::Settings {
+            self.view = View::Board;
+        }
+        if self.lock.blocks(&self.system.folders[self.nav.folder].id) {
+            self.nav.home();
+        }
+    }
+
+    // Out here rather than in the grid, so it never covers a button.
+    fn show_page_button(&mut self, ui: &mut egui::Ui, size: [f32; 2]) {
+        let folder = &self.system.folders[self.nav.folder];
+        if !folder.needs_pagination() {
+            return;
+        }
+
+        let step = Step::NextPage { folder: self.nav.folder, page: self.nav.page };
+        let label = self.catalog.fill("{}/{} ->", &[&(self.nav.page + 1).to_string(), &folder.pages().to_string()]);
+        let mut egui_button = egui::Button::new(label);
+        if self.modeling.as_ref().is_some_and(|m| m.is_current(step)) {
+            egui_button = highlighted(egui_button, ui, &self.theme);
+        }
+        if ui.add_sized(size, egui_button).clicked() {
+            navigation::advance(&mut self.modeling, step);
+            self.highlight = None;
+            self.nav.page = folder.next_page(self.nav.page);
+        }
+    }
+
+    // While locked: hold to unlock, or tap to enter the PIN. Otherwise: tap to lock.
+    fn show_lock_button(&mut self, ui: &mut egui::Ui, size: [f32; 2]) {
+        if !self.lock.locked {
+            if ui.add_sized(size, egui::Button::new("🔓")).on_hover_text(self.catalog.t("Lock")).clicked() {
+                self.lock_now();
+            }
+            return;
+        }
+
+        match self.settings.pin.clone() {
+            Some(pin) => {
+                if ui.add_sized(size, egui::Button::new("🔒")).clicked() {
+                    self.unlocking = !self.unlocking;
+                    self.pin_entry.clear();
+                }
+                if self.unlocking {
+                    let response = ui.add(egui::TextEdit::singleline(&mut self.pin_entry).password(true).hint_text(self.catalog.t("PIN")).desired_width(size[0]));
+                    if response.lost_focus() || ui.button(self.catalog.t("Unlock")).clicked() {
+                        if self.pin_entry == pin {
+                            self.lock.locked = false;
                         }
+                        self.pin_entry.clear();
+                        self.unlocking = false;
+                    }
+                }
+            },
+            None => {
+                let response = ui.add_sized(size, egui::Button::new("🔒").sense(egui::Sense::drag()));
+                if held(&mut self.hold_started, ui, &response, UNLOCK_HOLD_SECONDS) {
+                    self.lock.locked = false;
+                }
+            },
+        }
+    }
 
-                        if let Some(button) = hotbar.get_button(folder.cols, self.current_hotbar_page, col) {
-                            let egui_button = egui::Button::new(button.label.clone());
-                            if ui.add_sized(dimensions.button_size, egui_button).clicked() {
-                                self.panel.add_entry(button);
-                            }
+    fn open_editor(&mut self) {
+        self.editor = Some(Editor::new(&self.system, self.nav.folder, self.system_file.clone()));
+        self.editing_visibility = false;
+        self.modeling = None;
+        self.highlight = None;
+        self.view = View::Board;
+    }
+
+    // Swaps the dragged cell with the one it was dropped on.
+    fn finish_editor_drag(&mut self, ctx: &egui::Context) {
+        let Some(editor) = &mut self.editor else { return };
+        if editor.dragging.is_none() || !ctx.input(|i| i.pointer.any_released()) {
+            return;
+        }
+
+        let from = editor.dragging.take().unwrap_or_default();
+        let target = ctx.input(|i| i.pointer.interact_pos())
+            .and_then(|pos| editor.cells.iter().find(|(_, rect)| rect.contains(pos)))
+            .map(|(index, _)| *index);
+        if let Some(to) = target.filter(|to| *to != from) {
+            editor::swap_buttons(&mut self.system.folders[self.nav.folder], from, to);
+            editor.selected = Some(to);
+            editor.dirty = true;
+            self.search_index = SearchIndex::new(&self.system);
+        }
+    }
+
+    fn show_editor(&mut self, ui: &mut egui::Ui) {
+        let Some(editor) = &mut self.editor else { return };
+        if editor.folder != self.nav.folder {
+            editor.open(&self.system, self.nav.folder);
+        }
+
+        let folder_ids: Vec<String> = self.system.folders.iter().map(|f| f.id.clone()).collect();
+        let mut changed = false;
+        let mut done = false;
+
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            ui.heading(self.catalog.t("Button"));
+            let folder = &mut self.system.folders[self.nav.folder];
+            if let Some(index) = editor.selected {
+                let (page, col, row) = folder.position_of(index);
+                ui.label(self.catalog.fill("Page {}, row {}, column {}", &[&(page + 1).to_string(), &(row + 1).to_string(), &(col + 1).to_string()]));
+
+                if let Some(button) = folder.buttons.get_mut(index).and_then(|b| b.as_mut()) {
+                    ui.label(self.catalog.t("Label"));
+                    changed |= ui.text_edit_singleline(&mut button.label).changed();
+                    changed |= optional_text(ui, &self.catalog.t("Pronunciation"), &mut button.pronunciation);
+                    changed |= optional_text(ui, &self.catalog.t("Image"), &mut button.image);
+                    changed |= optional_text(ui, &self.catalog.t("Background color"), &mut button.background);
+                    changed |= optional_text(ui, &self.catalog.t("Border color"), &mut button.border);
+                    changed |= optional_text(ui, &self.catalog.t("Category"), &mut button.category);
+                    changed |= optional_text(ui, &self.catalog.t("Action"), &mut button.action);
+
+                    ui.label(self.catalog.t("Opens folder"));
+                    let current = button.folder.clone().unwrap_or_else(|| self.catalog.t("(none)"));
+                    egui::ComboBox::from_id_source("editor-folder-link").selected_text(current).show_ui(ui, |ui| {
+                        changed |= ui.selectable_value(&mut button.folder, None, self.catalog.t("(none)")).changed();
+                        for id in &folder_ids {
+                            changed |= ui.selectable_value(&mut button.folder, Some(id.clone()), id).changed();
+                        }
+                    });
+
+                    changed |= ui.checkbox(&mut button.hidden, self.catalog.t("Hidden for everyone")).changed();
+
+                    if ui.button(self.catalog.t("Delete button")).clicked() {
+                        editor::set_button(folder, index, None);
+                        changed = true;
+                    }
+                } else if ui.button(self.catalog.t("Add a button here")).clicked() {
+                    let mut button = Button::new("new".to_string(), None, None);
+                    button.parent = Some(folder.id.clone());
+                    editor::set_button(folder, index, Some(button));
+                    changed = true;
+                }
+            } else {
+                ui.label(self.catalog.t("Tap a cell to edit it, or drag it onto another to swap them."));
+            }
+
+            ui.separator();
+            ui.heading(self.catalog.t("Folder"));
+            ui.label(self.catalog.t("Name"));
+            changed |= ui.text_edit_singleline(&mut folder.name).changed();
+            ui.horizontal(|ui| {
+                ui.add(egui::DragValue::new(&mut editor.rows).clamp_range(1..=20).prefix(self.catalog.t("rows: ")));
+                ui.add(egui::DragValue::new(&mut editor.cols).clamp_range(1..=20).prefix(self.catalog.t("cols: ")));
+                if ui.button(self.catalog.t("Resize")).clicked() {
+                    editor::resize_folder(folder, editor.rows, editor.cols);
+                    editor.selected = None;
+                    changed = true;
+                }
+            });
+
+            ui.label(self.catalog.t("New folder name"));
+            ui.text_edit_singleline(&mut editor.new_folder_name);
+            ui.horizontal(|ui| {
+                let mut added = None;
+                if ui.button(self.catalog.t("Add inside this folder")).clicked() {
+                    added = Some(editor::add_folder(&mut self.system, &editor.new_folder_name, Some(self.nav.folder)));
+                }
+                if ui.button(self.catalog.t("Add at top level")).clicked() {
+                    added = Some(editor::add_folder(&mut self.system, &editor.new_folder_name, None));
+                }
+                match added {
+                    Some(Ok(_)) => {
+                        editor.new_folder_name.clear();
+                        editor.message = None;
+                        changed = true;
+                    },
+                    Some(Err(err)) => editor.message = Some(err),
+                    None => {},
+                }
+            });
+
+            if ui.button(self.catalog.t("Delete this folder")).clicked() {
+                match editor::delete_folder(&mut self.system, self.nav.folder) {
+                    Ok(()) => {
+                        self.nav = Navigation::new(0);
+                        editor.open(&self.system, 0);
+                        changed = true;
+                    },
+                    Err(err) => editor.message = Some(err),
+                }
+            }
+
+            ui.separator();
+            ui.heading(self.catalog.t("Saving"));
+            #[cfg(not(target_arch = "wasm32"))]
+            {
+                ui.label(self.catalog.t("File"));
+                ui.text_edit_singleline(&mut editor.path);
+            }
+            ui.horizontal(|ui| {
+                // There are no files to save to on the web, so the System is kept for this
+                // user instead, as if they'd opened it.
+                #[cfg(target_arch = "wasm32")]
+                if ui.button(self.catalog.t("Save")).clicked() {
+                    self.store_system = Some(self.system.serialize_json());
+                    editor.dirty = false;
+                    editor.message = Some(self.catalog.t("Saved on this device."));
+                }
+                #[cfg(not(target_arch = "wasm32"))]
+                if ui.button(self.catalog.t("Save")).clicked() {
+                    match self.system.save_file(&editor.path) {
+                        Ok(()) => {
+                            info!("Saved System to '{}'.", editor.path);
+                            editor.dirty = false;
+                            editor.message = Some(self.catalog.fill("Saved. The old version is in {}.bak.", &[&editor.path]));
+                        },
+                        Err(err) => editor.message = Some(self.catalog.fill("Couldn't save: {}", &[&err.to_string()])),
+                    }
+                }
+
+                done = ui.button(self.catalog.t("Done")).clicked();
+            });
+        });
+
+        // Search stays open beside the editor, so it has to keep up with every change.
+        if changed {
+            self.search_index = SearchIndex::new(&self.system);
+        }
+        if done {
+            self.editor = None;
+            return;
+        }
+
+        if changed {
+            editor.dirty = true;
+        }
+        if editor.dirty {
+            ui.label(self.catalog.t("There are unsaved changes."));
+        }
+        if let Some(message) = &editor.message {
+            ui.label(message);
+        }
+
+        ui.separator();
+        let mut pin = self.settings.pin.is_some();
+        if ui.checkbox(&mut pin, self.catalog.t("Require a PIN to edit")).changed() && !pin {
+            self.settings.pin = None;
+        }
+        if pin {
+            let mut value = self.settings.pin.clone().unwrap_or_default();
+            ui.add(egui::TextEdit::singleline(&mut value).password(true).hint_text(self.catalog.t("PIN")));
+            self.settings.pin = Some(value).filter(|v| !v.is_empty());
+        }
+    }
+
+    fn show_hotbar(&mut self, ui: &mut egui::Ui, dimensions: &Dimensions, actions: &mut Vec<Action>) {
+        let hotbar = &self.system.hotbar;
+        let folder = &self.system.folders[self.nav.folder];
+
+        for col in reading_order(folder.cols, self.catalog.rtl) {
+            if col == (folder.cols - 1) {
+                let label = self.catalog.fill("{}\n->", &[&(self.current_hotbar_page + 1).to_string()]);
+                let egui_button = egui::Button::new(label);
+                if ui.add_sized(dimensions.button_size, egui_button).clicked() {
+                    self.current_hotbar_page = hotbar.next_page(folder.cols, self.current_hotbar_page);
+                }
+                continue;
+            }
+
+            let cell = hotbar.get_button(folder.cols, self.current_hotbar_page, col)
+                .filter(|b| !b.hidden)
+                .filter(|b| self.editing_visibility || self.profile.is_visible(profile::HOTBAR, b.key()));
+
+            if let Some(button) = cell {
+                let visible = self.profile.is_visible(profile::HOTBAR, button.key());
+                let egui_button =
+                    if visible {
+                        colored(button.label.clone(), &self.system.button_colors(None, button), &self.theme)
+                    } else {
+                        egui::Button::new(egui::RichText::new(button.label.clone()).weak().strikethrough())
+                    };
+                if ui.add_sized(dimensions.button_size, egui_button).clicked() {
+                    if self.editing_visibility {
+                        self.profile.toggle(profile::HOTBAR, button.key());
+                    } else {
+                        self.events.push(Event::ButtonPressed { label: button.label.clone(), folder: None });
+                        self.lam.press(Source::Hotbar, None, Some(self.current_hotbar_page), Some(col), &button.label);
+                        if button.action.is_some() {
+                            queue_action(button, actions);
                         } else {
-                            // No button for this position on the hotbar.
-                            let egui_label = egui::Label::new("");
-                            //let egui_label = egui::Button::new("");
-                            ui.add_sized(dimensions.button_size, egui_label);
+                            self.panel.add_entry(button);
                         }
                     }
-                    ui.end_row();
-                });
+                }
+            } else {
+                // No button for this position on the hotbar.
+                let egui_label = egui::Label::new("");
+                //let egui_label = egui::Button::new("");
+                ui.add_sized(dimensions.button_size, egui_label);
+            }
+        }
+        ui.end_row();
+    }
 
-                // Row 2, Column 3
-                egui::Grid::new("related-words").show(ui, |ui| {
-                    if let Some(last_word) = self.panel.last_entry_label() {
-                        if let Some(related) = self.system.related.get(&last_word) {
-                            for row in 0..cmp::min(folder.rows, related.len()) {
-                                let related_idx = row;
-
-                                let button = &related[row];
-                                let egui_button = egui::Button::new(button.get_label(&self.system).clone());
-                                if ui.add_sized(dimensions.button_size, egui_button).clicked() {
-                                    self.panel.set_last_entry_related(related_idx);
-                                    self.panel.clear_last_entry_variant();
-                                }
-                                ui.end_row();
-                            }
+    fn show_related(&mut self, ui: &mut egui::Ui, layout: &Layout, dimensions: &Dimensions) {
+        let rows = self.system.folders[self.nav.folder].rows;
+
+        egui::Grid::new("related-words").show(ui, |ui| {
+            if let Some(last_word) = self.panel.last_entry_label() {
+                if let Some(related) = self.system.related.get(&last_word) {
+                    for row in 0..cmp::min(rows, related.len()) {
+                        let related_idx = row;
+
+                        let button = &related[row];
+                        let step = Step::Related { index: related_idx };
+                        let mut egui_button = egui::Button::new(i18n::display(&button.get_label(&self.system)));
+                        if self.modeling.as_ref().is_some_and(|m| m.is_current(step)) {
+                            egui_button = highlighted(egui_button, ui, &self.theme);
+                        }
+                        if ui.add_sized(dimensions.sized(layout.related_width), egui_button).clicked() {
+                            navigation::advance(&mut self.modeling, step);
+                            self.lam.press(Source::Related, None, None, Some(related_idx), &button.get_label(&self.system));
+                            self.panel.set_last_entry_related(related_idx);
+                            self.panel.clear_last_entry_variant();
                         }
+                        ui.end_row();
                     }
-                });
+                }
+            }
+        });
+    }
 
-                // Row 2, Column 4
-                egui::Grid::new("variant-words").show(ui, |ui| {
-                    if let Some(last_word) = self.panel.last_entry_related_label(&self.system) {
-                        if let Some(variants) = self.system.variants.get(&last_word) {
-                            for row in 0..cmp::min(folder.rows, variants.len()) {
-                                let variant = row;
-
-                                let button = &variants[variant];
-                                let egui_button = egui::Button::new(button.get_label(&self.system).clone());
-                                if ui.add_sized(dimensions.button_size, egui_button).clicked() {
-                                    self.panel.set_last_entry_variant(variant);
-                                }
-                                ui.end_row();
-                            }
+    fn show_variants(&mut self, ui: &mut egui::Ui, layout: &Layout, dimensions: &Dimensions) {
+        let rows = self.system.folders[self.nav.folder].rows;
+
+        egui::Grid::new("variant-words").show(ui, |ui| {
+            if let Some(last_word) = self.panel.last_entry_related_label(&self.system) {
+                if let Some(variants) = self.system.variants.get(&last_word) {
+                    for row in 0..cmp::min(rows, variants.len()) {
+                        let variant = row;
+
+                        let button = &variants[variant];
+                        let step = Step::Variant { index: variant };
+                        let mut egui_button = egui::Button::new(i18n::display(&button.get_label(&self.system)));
+                        if self.modeling.as_ref().is_some_and(|m| m.is_current(step)) {
+                            egui_button = highlighted(egui_button, ui, &self.theme);
+                        }
+                        if ui.add_sized(dimensions.sized(layout.variants_width), egui_button).clicked() {
+                            navigation::advance(&mut self.modeling, step);
+                            self.lam.press(Source::Variants, None, None, Some(variant), &button.get_label(&self.system));
+                            self.panel.set_last_entry_variant(variant);
                         }
+                        ui.end_row();
+                    }
+                }
+            }
+        });
+    }
+
+    /// Everything `update` does, apart from talking to the window.
+    pub fn draw(&mut self, ctx: &egui::Context) {
+        if self.picking_user {
+            self.show_user_picker(ctx);
+            return;
+        }
+
+        let mut actions: Vec<Action> = vec![];
+        for loaded in self.loader.take() {
+            if let Err(err) = loaded.contents.and_then(|contents| self.load_system(&loaded.source, &contents)) {
+                warn!("!!! Failed to load a System from {}: {}", loaded.source, err);
+                self.system_error = Some(self.catalog.fill("Couldn't load a System from {}: {}", &[&loaded.source, &err]));
+            }
+        }
+        if let Some(err) = self.system_error.clone() {
+            egui::Window::new(self.catalog.t("System not loaded")).collapsible(false).show(ctx, |ui| {
+                ui.label(err);
+                ui.label(self.catalog.fill("Still using {}.", &[&self.system.name]));
+                if ui.button(self.catalog.t("OK")).clicked() {
+                    self.system_error = None;
+                }
+            });
+        }
+
+        // Not while locked; whoever unlocks it can update from the settings.
+        if self.apply_update.is_some() && !self.update_dismissed && !self.lock.locked {
+            egui::Window::new(self.catalog.t("Update available")).collapsible(false).show(ctx, |ui| {
+                ui.label(self.catalog.t("A new version of SGS is ready. Updating restarts the app."));
+                ui.horizontal(|ui| {
+                    if ui.button(self.catalog.t("Update now")).clicked() {
+                        self.update_now();
+                    }
+                    if ui.button(self.catalog.t("Later")).clicked() {
+                        self.update_dismissed = true;
                     }
                 });
             });
+        }
+
+        let panel_len = self.panel.entries.len();
+
+        if self.restyle {
+            self.theme.apply(ctx);
+            self.restyle = false;
+        }
+
+        if self.editor.is_some() {
+            egui::SidePanel::right("editor").show(ctx, |ui| self.show_editor(ui));
+        }
+
+        let screen = ctx.available_rect();
+        let screen_size = egui::pos2(screen.width(), screen.height());
+        let layout = self.layout();
+        let (rows, cols) = self.system.grid_size();
+        let dimensions = Dimensions::new(screen_size, rows, cols, &layout);
+
+        let inner_margin = egui::style::Margin::same(dimensions.margin);
+        ctx.style_mut(|style| {
+            style.spacing.item_spacing = egui::vec2(dimensions.item_spacing, dimensions.item_spacing);
         });
+        let frame = egui::containers::Frame::central_panel(&ctx.style()).inner_margin(inner_margin);
+
+        if dimensions.portrait && layout.has_drawer() {
+            egui::TopBottomPanel::bottom("drawer").frame(frame).show(ctx, |ui| {
+                self.show_drawer(ui, &layout, &dimensions);
+            });
+        }
+
+        egui::CentralPanel::default().frame(frame).show(ctx, |ui| {
+            // Only scrolls once buttons are as small as they're allowed to get.
+            egui::ScrollArea::both().drag_to_scroll(false).show(ui, |ui| {
+                if dimensions.portrait {
+                    self.show_portrait(ui, &layout, &dimensions, &mut actions);
+                    return;
+                }
+
+                egui::Grid::new("main-grid").show(ui, |ui| {
+                    // By default, with each [] being a sub-grid:
+                    // | [Speak]           | [Panel]  | [Delete]  | [Clear]    |
+                    // -----------------------------------------------------------
+                    // | [Folder selector] | [system] | [related] | [variants] |
+                    for region in layout.top_row() {
+                        self.show_region(ui, region, &layout, &dimensions, &mut actions);
+                    }
+                    ui.end_row();
+
+                    for region in layout.bottom_row() {
+                        self.show_region(ui, region, &layout, &dimensions, &mut actions);
+                    }
+                });
+            });
+        });
+
+        self.finish_editor_drag(ctx);
+        if let Some(editor) = &mut self.editor {
+            editor.cells.clear();
+        }
+
+        for action in actions {
+            self.perform(&action);
+        }
+
+        if self.settings.speak_each_word && self.panel.entries.len() > panel_len {
+            if let Some(entry) = self.panel.entries.last() {
+                let text = entry.get_pronouncible_text(&self.system);
+                speak(&mut self.speech_engine, &mut self.events, text);
+            }
+        }
+
+        let folder = &self.system.folders[self.nav.folder].id;
+        if self.shown_folder.as_ref() != Some(folder) {
+            self.shown_folder = Some(folder.clone());
+            self.events.push(Event::FolderChanged(folder.clone()));
+        }
+        for event in std::mem::take(&mut self.events) {
+            if let Event::Spoken(text) = &event {
+                self.lam.spoken(text);
+            }
+            for listener in &mut self.listeners {
+                listener(&event);
+            }
+        }
+    }
+}
+
+impl eframe::App for App {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.users.save(storage);
+        let mut storage = UserStorage::new(storage, self.users.current());
+        self.settings.save(&mut storage);
+        self.profile.save(&mut storage);
+        self.lock.save(&mut storage);
+        self.lam.save(&mut storage);
+        if let Some(json) = self.store_system.take() {
+            eframe::Storage::set_string(&mut storage, loader::STORAGE_KEY, json);
+        }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    fn as_any_mut(&mut self) -> Option<&mut dyn std::any::Any> {
+        Some(&mut *self)
+    }
+
+    // Closing the window is one of the things the lock is for.
+    #[cfg(not(target_arch = "wasm32"))]
+    fn on_close_event(&mut self) -> bool {
+        !self.lock.locked
+    }
+
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        // No title bar (and so no close button) while locked.
+        #[cfg(not(target_arch = "wasm32"))]
+        if self.decorations_locked != Some(self.lock.locked) {
+            frame.set_decorations(!self.lock.locked);
+            self.decorations_locked = Some(self.lock.locked);
+        }
+
+        if let Some(index) = self.switch_user.take() {
+            if let Some(storage) = frame.storage_mut() {
+                self.change_user(index, storage);
+            } else {
+                warn!("!!! Can't switch users without storage to keep their things in.");
+            }
+        }
+
+        self.draw(ctx);
     }
 }
@@ -1,13 +1,25 @@
+use crate::action::Action;
+use crate::color::Colors;
 use crate::system::System;
 use nanoserde::{DeJson, SerJson};
 
 #[derive(Clone, Debug, DeJson, SerJson, PartialEq)]
 pub struct Button {
+    /// Stays the same when the label changes, so the button can be tracked across edits.
+    pub id: Option<String>,
     pub label: String,
     pub parent: Option<String>,
     pub pronunciation: Option<String>,
     pub image: Option<String>,
     pub folder: Option<String>,
+    pub action: Option<String>,
+    pub background: Option<String>,
+    pub border: Option<String>,
+    // A key in the System's color scheme, e.g. "noun".
+    pub category: Option<String>,
+    /// Hidden buttons leave an empty space, so nothing else moves.
+    #[nserde(default)]
+    pub hidden: bool,
     related: Option<usize>,
     variant: Option<usize>,
 }
@@ -17,8 +29,27 @@ impl Button {
         let related = None;
         let variant = None;
         let folder = None;
+        let action = None;
+        let background = None;
+        let border = None;
+        let category = None;
         let parent = None;
-        Self { label, parent, pronunciation, image, folder, related, variant }
+        let id = None;
+        let hidden = false;
+        Self { id, label, parent, pronunciation, image, folder, action, background, border, category, hidden, related, variant }
+    }
+
+    /// The id if there is one, otherwise the label.
+    pub fn key(&self) -> &str {
+        self.id.as_deref().unwrap_or(&self.label)
+    }
+
+    pub fn get_action(&self) -> Option<Action> {
+        Action::parse(self.action.as_ref()?)
+    }
+
+    pub fn colors(&self) -> Colors {
+        Colors { background: self.background.clone(), border: self.border.clone(), text: None }
     }
 
     pub fn get_related_word_label(&self, system: &System) -> String {
@@ -1,33 +1,165 @@
 use log::warn; // also available: info, trace.
 
+use crate::diff;
+use crate::format::{self, Format};
+use crate::pdf::{self, Paper};
+use crate::positions;
+use crate::lam;
+use crate::motor::{Analysis, Frequencies};
+use crate::render;
+use crate::report::{self, Report};
+use crate::settings::Settings;
+use crate::speech::SpeechEngine;
+use crate::stats::Stats;
+use crate::system::System;
+
+use nanoserde::SerJson;
+
 const HELP: &str = "\
 USAGE: sgs [OPTIONS]
+       sgs COMMAND [ARGS]
 
 OPTIONS:
     -h, --help      Print this help text
     --system FILE   Load the system specified by FILE.
-                    FILE must be valid JSON.
+                    FILE must be valid JSON or the #sgs text format.
                     (Default: Use the bundled system.)
+    --translations FILE
+                    Offer the translations in FILE, a JSON message catalog
+                    like those in locales/. May be given more than once.
+
+COMMANDS:
+    validate FILE   Check FILE for problems, e.g. links to missing folders.
+                    Exits with status 1 if there are any.
+    convert IN OUT  Convert IN to the format matching OUT's extension
+                    (.json, or .sgs/.txt for the text format).
+    stats FILE      List folders, buttons and pages, duplicate words, and
+                    words without a pronunciation.
+    speak TEXT      Speak TEXT and exit. Use --voice NAME to pick a voice.
+    render FILE OUT.png
+                    Draw a board from FILE to a PNG image, without a window.
+                    --folder ID     The folder to draw (default: the first).
+                    --page N        Its page, starting from 1 (default: 1).
+                    --size WxH      The screen size (default: 1280x720).
+                    --theme NAME    The theme to use (default: default).
+    diff OLD NEW    List what changed from OLD to NEW, folder by folder:
+                    buttons added, removed, relabeled or moved, and folders
+                    resized. Exits with status 1 if anything changed.
+                    --moved         Only list buttons that moved to another
+                                    page, row or column, or were removed.
+    merge BASE OURS THEIRS
+                    Merge the changes from BASE to THEIRS into OURS, button
+                    by button. Buttons changed on both sides are marked with
+                    <<<<<<< in their labels. Exits with status 1 if any were.
+                    Works as a git merge driver: sgs merge %O %A %B
+                    --output FILE   Write the result here instead of to OURS.
+    pdf FILE OUT.pdf
+                    Make printable boards from FILE: one page for each page
+                    of each folder.
+                    --paper SIZE    letter or a4 (default: letter).
+    motor FILE      Count the taps it takes to reach each word in FILE, from
+                    the folder selector, and list unreachable words.
+                    --frequencies LIST
+                                    A word list, most frequent first, with
+                                    an optional count after each word. Adds
+                                    frequent words that take 5+ taps, and
+                                    the words worth moving to the hotbar.
+    report LOG.csv  Summarize an activity log exported from Settings: the
+                    most-used words, different words per day and week, mean
+                    length of utterance, taps per word, and words never used.
+                    --system FILE   The System the log is from (default:
+                                    the bundled system).
+                    --format FMT    md or html (default: md).
+                    --output FILE   Write the report here instead of to
+                                    standard output.
+
+Commands exit with status 2 if FILE can't be loaded.
 ";
 
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Validate(String),
+    Convert(String, String),
+    Stats(String),
+    Speak { text: String, voice: Option<String> },
+    Render { input: String, output: String, folder: Option<String>, page: usize, size: (usize, usize), theme: Option<String> },
+    Pdf { input: String, output: String, paper: Paper },
+    Diff { old: String, new: String, moved: bool },
+    Merge { base: String, ours: String, theirs: String, output: Option<String> },
+    Motor { input: String, frequencies: Option<String> },
+    Report { log: String, system: Option<String>, format: report::Format, output: Option<String> },
+}
+
+fn parse_size(size: &str) -> Result<(usize, usize), String> {
+    let parsed = size.split_once('x').and_then(|(w, h)| Some((w.parse().ok()?, h.parse().ok()?)));
+    parsed.ok_or_else(|| format!("expected a size like 1280x720, got '{}'", size))
+}
+
 #[derive(Debug)]
 pub struct AppArgs {
     pub help: bool,
     pub system: Option<String>,
+    pub translations: Vec<String>,
+    pub command: Option<Command>,
 }
 
 pub fn parse_args() -> Result<AppArgs, pico_args::Error> {
     // If std::env::args() is empty, there's nothing to parse.
     // This happens, e.g., when doing a wasm build.
     if std::env::args().count() == 0 {
-        return Ok(AppArgs { help: false, system: None });
+        return Ok(AppArgs { help: false, system: None, translations: vec![], command: None });
     }
 
     let mut pargs = pico_args::Arguments::from_env();
 
+    let command = match pargs.subcommand()?.as_deref() {
+        Some("validate") => Some(Command::Validate(pargs.free_from_str()?)),
+        Some("convert") => Some(Command::Convert(pargs.free_from_str()?, pargs.free_from_str()?)),
+        Some("stats") => Some(Command::Stats(pargs.free_from_str()?)),
+        Some("speak") => Some(Command::Speak { voice: pargs.opt_value_from_str("--voice")?, text: pargs.free_from_str()? }),
+        Some("render") => Some(Command::Render {
+            folder: pargs.opt_value_from_str("--folder")?,
+            page: pargs.opt_value_from_str("--page")?.unwrap_or(1),
+            size: pargs.opt_value_from_fn("--size", parse_size)?.unwrap_or((1280, 720)),
+            theme: pargs.opt_value_from_str("--theme")?,
+            input: pargs.free_from_str()?,
+            output: pargs.free_from_str()?,
+        }),
+        Some("pdf") => Some(Command::Pdf {
+            paper: pargs.opt_value_from_fn("--paper", |s| Paper::parse(s).ok_or(format!("unknown paper size '{}'", s)))?.unwrap_or(Paper::LETTER),
+            input: pargs.free_from_str()?,
+            output: pargs.free_from_str()?,
+        }),
+        Some("diff") => Some(Command::Diff {
+            moved: pargs.contains("--moved"),
+            old: pargs.free_from_str()?,
+            new: pargs.free_from_str()?,
+        }),
+        Some("merge") => Some(Command::Merge {
+            output: pargs.opt_value_from_str("--output")?,
+            base: pargs.free_from_str()?,
+            ours: pargs.free_from_str()?,
+            theirs: pargs.free_from_str()?,
+        }),
+        Some("motor") => Some(Command::Motor {
+            frequencies: pargs.opt_value_from_str("--frequencies")?,
+            input: pargs.free_from_str()?,
+        }),
+        Some("report") => Some(Command::Report {
+            system: pargs.opt_value_from_str("--system")?,
+            format: pargs.opt_value_from_fn("--format", |s| report::Format::parse(s).ok_or(format!("unknown report format '{}'", s)))?.unwrap_or(report::Format::Markdown),
+            output: pargs.opt_value_from_str("--output")?,
+            log: pargs.free_from_str()?,
+        }),
+        Some(other) => return Err(pico_args::Error::ArgumentParsingFailed { cause: format!("unknown command '{}'", other) }),
+        None => None,
+    };
+
     let args = AppArgs {
         help: pargs.contains(["-h", "--help"]),
         system: pargs.opt_value_from_str("--system")?,
+        translations: pargs.values_from_str("--translations")?,
+        command,
     };
 
     // It's up to the caller what to do with the remaining arguments.
@@ -39,9 +171,175 @@ pub fn parse_args() -> Result<AppArgs, pico_args::Error> {
     Ok(args)
 }
 
+fn load_or_exit(path: &str) -> System {
+    match System::load_file(path) {
+        Ok(system) => system,
+        Err(err) => {
+            eprintln!("{}: {}", path, err);
+            std::process::exit(2);
+        },
+    }
+}
+
+fn bundled_system() -> System {
+    System::load_str(include_str!("../system-wiki2016.json")).expect("Failed to load bundled System. (This should never happen.)")
+}
+
+/// Writes `system` in the format matching `path`'s extension.
+fn write_system(system: &System, path: &str) -> std::io::Result<()> {
+    let contents = match Format::from_path(path) {
+        Format::Json => system.serialize_json(),
+        Format::Text => {
+            let (text, lost) = format::to_text(system);
+            for what in lost {
+                warn!("!!! Not supported by the text format, left out: {}", what);
+            }
+            text
+        },
+    };
+    std::fs::write(path, contents)
+}
+
+/// Runs `command`, returning the exit status.
+fn run(command: Command) -> i32 {
+    match command {
+        Command::Validate(path) => {
+            let problems = load_or_exit(&path).problems();
+            for problem in &problems {
+                println!("{}: {}", path, problem);
+            }
+            println!("{} problem(s) found.", problems.len());
+            if problems.is_empty() { 0 } else { 1 }
+        },
+        Command::Convert(input, output) => {
+            if let Err(err) = write_system(&load_or_exit(&input), &output) {
+                eprintln!("{}: {}", output, err);
+                return 2;
+            }
+            0
+        },
+        Command::Stats(path) => {
+            print!("{}", Stats::new(&load_or_exit(&path)));
+            0
+        },
+        Command::Speak { text, voice } => {
+            let result = SpeechEngine::default().and_then(|mut speech| {
+                if let Some(voice) = voice {
+                    speech.set_voice(&voice)?;
+                }
+                speech.speak(text.as_str())?;
+                // Speech happens in the background, so wait for it to finish before exiting.
+                while speech.is_speaking() {
+                    std::thread::sleep(std::time::Duration::from_millis(100));
+                }
+                Ok(())
+            });
+            if let Err(err) = result {
+                eprintln!("Could not speak: {}", err);
+                return 1;
+            }
+            0
+        },
+        Command::Render { input, output, folder, page, size: (width, height), theme } => {
+            let system = load_or_exit(&input);
+            let folder = folder.or_else(|| system.folders.first().map(|f| f.id.clone())).unwrap_or_default();
+            let mut settings = Settings::default();
+            if let Some(theme) = theme {
+                settings.theme = theme;
+            }
+
+            let result = render::render(&system, &folder, page.saturating_sub(1), width, height, settings)
+                .map_err(|err| err.into())
+                .and_then(|image| image.to_png())
+                .and_then(|png| Ok(std::fs::write(&output, png)?));
+            if let Err(err) = result {
+                eprintln!("Could not render '{}': {}", output, err);
+                return 1;
+            }
+            0
+        },
+        Command::Diff { old, new, moved: true } => {
+            let moves = positions::moved_buttons(&load_or_exit(&old), &load_or_exit(&new));
+            for m in &moves {
+                println!("{}", m);
+            }
+            println!("{} button(s) moved or removed.", moves.len());
+            if moves.is_empty() { 0 } else { 1 }
+        },
+        Command::Diff { old, new, moved: false } => {
+            let diffs = diff::diff(&load_or_exit(&old), &load_or_exit(&new));
+            for folder in &diffs {
+                print!("{}", folder);
+            }
+            if diffs.is_empty() { 0 } else { 1 }
+        },
+        Command::Merge { base, ours, theirs, output } => {
+            let merged = diff::merge(&load_or_exit(&base), &load_or_exit(&ours), &load_or_exit(&theirs));
+            let output = output.unwrap_or(ours);
+            if let Err(err) = write_system(&merged.system, &output) {
+                eprintln!("{}: {}", output, err);
+                return 2;
+            }
+            for conflict in &merged.conflicts {
+                println!("CONFLICT: {}", conflict);
+            }
+            if merged.conflicts.is_empty() { 0 } else { 1 }
+        },
+        Command::Pdf { input, output, paper } => {
+            let system = load_or_exit(&input);
+            // Images are relative to the System file.
+            let base = std::path::Path::new(&input).parent();
+            if let Err(err) = std::fs::write(&output, pdf::export(&system, paper, base)) {
+                eprintln!("{}: {}", output, err);
+                return 1;
+            }
+            0
+        },
+        Command::Motor { input, frequencies } => {
+            let system = load_or_exit(&input);
+            let frequencies = match frequencies.map(|path| std::fs::read_to_string(&path).map_err(|err| (path, err))).transpose() {
+                Ok(list) => list.map(|list| Frequencies::parse(&list)),
+                Err((path, err)) => {
+                    eprintln!("{}: {}", path, err);
+                    return 2;
+                },
+            };
+            print!("{}", Analysis::new(&system, frequencies.as_ref()));
+            0
+        },
+        Command::Report { log, system, format, output } => {
+            let system = system.map(|path| load_or_exit(&path)).unwrap_or_else(bundled_system);
+            let records = std::fs::read_to_string(&log).map_err(|err| err.to_string()).and_then(|csv| lam::from_csv(&csv));
+            let records = match records {
+                Ok(records) => records,
+                Err(err) => {
+                    eprintln!("{}: {}", log, err);
+                    return 2;
+                },
+            };
+
+            let report = Report::new(&system, &records).render(format);
+            match output {
+                Some(output) => {
+                    if let Err(err) = std::fs::write(&output, report) {
+                        eprintln!("{}: {}", output, err);
+                        return 1;
+                    }
+                },
+                None => print!("{}", report),
+            }
+            0
+        },
+    }
+}
+
 pub fn process_args(args: AppArgs) {
     if args.help {
         println!("{}", HELP);
         std::process::exit(0);
     }
+
+    if let Some(command) = args.command {
+        std::process::exit(run(command));
+    }
 }
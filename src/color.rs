@@ -0,0 +1,131 @@
+use eframe::egui::Color32;
+use nanoserde::{DeJson, SerJson};
+
+/// WCAG AA contrast for normal-sized text.
+pub const MIN_CONTRAST: f32 = 4.5;
+
+/// Colors for a button, as "#RRGGBB" (or "#RGB") strings.
+///
+/// Used for entries in a System's color scheme, and for resolving what a
+/// button should look like. Anything left as None falls back to the theme.
+#[derive(Clone, Debug, Default, DeJson, SerJson, PartialEq)]
+pub struct Colors {
+    pub background: Option<String>,
+    pub border: Option<String>,
+    /// Label color. If unset, black or white is picked, whichever is more readable.
+    pub text: Option<String>,
+}
+
+impl Colors {
+    /// Fills in anything unset with values from `other`.
+    pub fn inherit(&mut self, other: &Colors) {
+        if self.background.is_none() {
+            self.background = other.background.clone();
+        }
+        if self.border.is_none() {
+            self.border = other.border.clone();
+        }
+        if self.text.is_none() {
+            self.text = other.text.clone();
+        }
+    }
+
+    pub fn background(&self) -> Option<Color32> {
+        parse_hex(self.background.as_ref()?)
+    }
+
+    pub fn border(&self) -> Option<Color32> {
+        parse_hex(self.border.as_ref()?)
+    }
+
+    /// The label color to use on this background, or None to use the theme's.
+    ///
+    /// A `text` color that's too hard to read is ignored.
+    pub fn text(&self) -> Option<Color32> {
+        let background = self.background()?;
+        let text = self.text.as_ref().and_then(|t| parse_hex(t));
+
+        match text {
+            Some(text) if contrast_ratio(text, background) >= MIN_CONTRAST => Some(text),
+            _ => Some(text_color_for(background)),
+        }
+    }
+}
+
+pub fn parse_hex(color: &str) -> Option<Color32> {
+    let hex = color.trim().strip_prefix('#')?;
+    if !hex.is_ascii() {
+        return None;
+    }
+
+    let channel = |s: &str| u8::from_str_radix(s, 16).ok();
+    match hex.len() {
+        3 => {
+            let r = channel(&hex[0..1])?;
+            let g = channel(&hex[1..2])?;
+            let b = channel(&hex[2..3])?;
+            Some(Color32::from_rgb(r * 17, g * 17, b * 17))
+        },
+        6 => Some(Color32::from_rgb(channel(&hex[0..2])?, channel(&hex[2..4])?, channel(&hex[4..6])?)),
+        _ => None,
+    }
+}
+
+// https://www.w3.org/TR/WCAG21/#dfn-relative-luminance
+fn relative_luminance(color: Color32) -> f32 {
+    let linear = |c: u8| {
+        let c = (c as f32) / 255.0;
+        if c <= 0.03928 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+
+    0.2126 * linear(color.r()) + 0.7152 * linear(color.g()) + 0.0722 * linear(color.b())
+}
+
+// https://www.w3.org/TR/WCAG21/#dfn-contrast-ratio
+pub fn contrast_ratio(a: Color32, b: Color32) -> f32 {
+    let a = relative_luminance(a);
+    let b = relative_luminance(b);
+    let (lighter, darker) = if a > b { (a, b) } else { (b, a) };
+
+    (lighter + 0.05) / (darker + 0.05)
+}
+
+/// Black or white, whichever is easier to read on `background`.
+///
+/// One of them always reaches at least 4.5:1, so this always meets [`MIN_CONTRAST`].
+pub fn text_color_for(background: Color32) -> Color32 {
+    if contrast_ratio(Color32::BLACK, background) >= contrast_ratio(Color32::WHITE, background) {
+        Color32::BLACK
+    } else {
+        Color32::WHITE
+    }
+}
+
+#[test]
+fn test_colors() {
+    assert_eq!(Some(Color32::from_rgb(255, 204, 0)), parse_hex("#ffcc00"));
+    assert_eq!(Some(Color32::from_rgb(255, 204, 0)), parse_hex("#FC0"));
+    assert_eq!(None, parse_hex("ffcc00"));
+    assert_eq!(None, parse_hex("#ffcc0"));
+    assert_eq!(None, parse_hex("#gggggg"));
+
+    assert!((contrast_ratio(Color32::BLACK, Color32::WHITE) - 21.0).abs() < 0.01);
+    assert_eq!(Color32::BLACK, text_color_for(parse_hex("#ffe97a").unwrap()));
+    assert_eq!(Color32::WHITE, text_color_for(parse_hex("#1a237e").unwrap()));
+
+    // Every background gets readable text.
+    for gray in 0..=255 {
+        let background = Color32::from_rgb(gray, gray, gray);
+        assert!(contrast_ratio(text_color_for(background), background) >= MIN_CONTRAST);
+    }
+
+    let mut colors = Colors { background: Some("#ffffff".to_string()), text: Some("#eeeeee".to_string()), ..Default::default() };
+    assert_eq!(Some(Color32::BLACK), colors.text());
+    colors.inherit(&Colors { border: Some("#000".to_string()), background: Some("#000".to_string()), text: None });
+    assert_eq!(Some("#ffffff".to_string()), colors.background);
+    assert_eq!(Some(Color32::BLACK), colors.border());
+}
@@ -0,0 +1,392 @@
+//! Comparing and merging versions of a System, button by button, instead of
+//! line by line like git would.
+
+use crate::button::Button;
+use crate::positions::Position;
+use crate::system::{Folder, System};
+
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::fmt;
+
+/// Labels of buttons that couldn't be merged start with this.
+pub const CONFLICT_MARKER: &str = "<<<<<<<";
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+    FolderAdded,
+    FolderRemoved,
+    Resized { from: (usize, usize), to: (usize, usize) },
+    /// Anything else about the folder itself, e.g. its name or colors.
+    FolderChanged(Vec<&'static str>),
+    Added { button: String, at: Position },
+    Removed { button: String, at: Position },
+    Relabeled { from: String, to: String, at: Position },
+    Moved { button: String, from: Position, to: Position },
+    /// Same button, same place, but e.g. a different pronunciation.
+    Changed { button: String, at: Position, fields: Vec<&'static str> },
+    /// Something that isn't part of a folder, e.g. "variants".
+    Other(&'static str),
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::FolderAdded => write!(f, "+ folder added"),
+            Self::FolderRemoved => write!(f, "- folder removed"),
+            Self::Resized { from, to } => write!(f, "~ resized from {}x{} to {}x{}", from.0, from.1, to.0, to.1),
+            Self::FolderChanged(fields) => write!(f, "~ changed: {}", fields.join(", ")),
+            Self::Added { button, at } => write!(f, "+ '{}' at {}", button, at),
+            Self::Removed { button, at } => write!(f, "- '{}' at {}", button, at),
+            Self::Relabeled { from, to, at } => write!(f, "~ '{}' relabeled '{}' at {}", from, to, at),
+            Self::Moved { button, from, to } => write!(f, "> '{}' moved from {} to {}", button, from, to),
+            Self::Changed { button, at, fields } => write!(f, "~ '{}' at {}: {} changed", button, at, fields.join(", ")),
+            Self::Other(what) => write!(f, "~ {} changed", what),
+        }
+    }
+}
+
+/// The changes to one folder. The hotbar is "hotbar", and everything else is "system".
+#[derive(Clone, Debug, PartialEq)]
+pub struct FolderDiff {
+    pub folder: String,
+    pub changes: Vec<Change>,
+}
+
+impl fmt::Display for FolderDiff {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{}:", self.folder)?;
+        for change in &self.changes {
+            writeln!(f, "    {}", change)?;
+        }
+        Ok(())
+    }
+}
+
+fn button_fields(a: &Button, b: &Button) -> Vec<&'static str> {
+    let fields = [
+        ("pronunciation", a.pronunciation != b.pronunciation),
+        ("image", a.image != b.image),
+        ("folder", a.folder != b.folder),
+        ("action", a.action != b.action),
+        ("background", a.background != b.background),
+        ("border", a.border != b.border),
+        ("category", a.category != b.category),
+        ("hidden", a.hidden != b.hidden),
+        ("parent", a.parent != b.parent),
+    ];
+    fields.into_iter().filter(|(_, changed)| *changed).map(|(name, _)| name).collect()
+}
+
+fn folder_fields(a: &Folder, b: &Folder) -> Vec<&'static str> {
+    let fields = [
+        ("name", a.name != b.name),
+        ("immediate", a.immediate != b.immediate),
+        ("toplevel", a.toplevel != b.toplevel),
+        ("background", a.background != b.background),
+        ("border", a.border != b.border),
+        ("category", a.category != b.category),
+    ];
+    fields.into_iter().filter(|(_, changed)| *changed).map(|(name, _)| name).collect()
+}
+
+/// Buttons are matched by id (or label), like [`crate::positions::moved_buttons`].
+/// A button that doesn't match anything, in a cell whose old button doesn't either,
+/// was relabeled.
+fn diff_buttons(old: &[Option<Button>], new: &[Option<Button>], old_at: &dyn Fn(usize) -> Position, new_at: &dyn Fn(usize) -> Position) -> Vec<Change> {
+    let mut changes = vec![];
+    let mut unmatched_new: Vec<bool> = new.iter().map(|b| b.is_some()).collect();
+    let mut by_key: HashMap<&str, VecDeque<usize>> = HashMap::new();
+    for (index, button) in new.iter().enumerate() {
+        if let Some(button) = button {
+            by_key.entry(button.key()).or_default().push_back(index);
+        }
+    }
+
+    let mut unmatched_old = vec![];
+    for (i, button) in old.iter().enumerate() {
+        let Some(button) = button else { continue };
+        let Some(j) = by_key.get_mut(button.key()).and_then(|indexes| indexes.pop_front()) else {
+            unmatched_old.push(i);
+            continue;
+        };
+        unmatched_new[j] = false;
+        let other = new[j].as_ref().unwrap();
+
+        if old_at(i) != new_at(j) {
+            changes.push(Change::Moved { button: button.label.clone(), from: old_at(i), to: new_at(j) });
+        }
+        if button.label != other.label {
+            changes.push(Change::Relabeled { from: button.label.clone(), to: other.label.clone(), at: new_at(j) });
+        }
+        let fields = button_fields(button, other);
+        if !fields.is_empty() {
+            changes.push(Change::Changed { button: other.label.clone(), at: new_at(j), fields });
+        }
+    }
+
+    for i in unmatched_old {
+        let button = old[i].as_ref().unwrap();
+        if unmatched_new.get(i) == Some(&true) {
+            unmatched_new[i] = false;
+            let other = new[i].as_ref().unwrap();
+            changes.push(Change::Relabeled { from: button.label.clone(), to: other.label.clone(), at: new_at(i) });
+            let fields = button_fields(button, other);
+            if !fields.is_empty() {
+                changes.push(Change::Changed { button: other.label.clone(), at: new_at(i), fields });
+            }
+        } else {
+            changes.push(Change::Removed { button: button.label.clone(), at: old_at(i) });
+        }
+    }
+
+    for (j, unmatched) in unmatched_new.into_iter().enumerate() {
+        if unmatched {
+            changes.push(Change::Added { button: new[j].as_ref().unwrap().label.clone(), at: new_at(j) });
+        }
+    }
+
+    changes
+}
+
+/// Everything that changed from `old` to `new`, folder by folder. Unchanged folders are left out.
+pub fn diff(old: &System, new: &System) -> Vec<FolderDiff> {
+    let mut diffs = vec![];
+    let mut add = |folder: &str, changes: Vec<Change>| {
+        if !changes.is_empty() {
+            diffs.push(FolderDiff { folder: folder.to_string(), changes });
+        }
+    };
+
+    let others = [
+        ("name", old.name != new.name),
+        ("description", old.description != new.description),
+        ("variants", old.variants != new.variants),
+        ("related", old.related != new.related),
+        ("macros", old.macros != new.macros),
+        ("color scheme", old.color_scheme != new.color_scheme),
+        ("themes", old.themes != new.themes),
+        ("layout", old.layout != new.layout),
+    ];
+    add("system", others.into_iter().filter(|(_, changed)| *changed).map(|(what, _)| Change::Other(what)).collect());
+
+    for old_folder in &old.folders {
+        let Some(new_folder) = new.folder_index(&old_folder.id).map(|idx| &new.folders[idx]) else {
+            add(&old_folder.id, vec![Change::FolderRemoved]);
+            continue;
+        };
+        if old_folder == new_folder {
+            continue;
+        }
+
+        let mut changes = vec![];
+        if (old_folder.rows, old_folder.cols) != (new_folder.rows, new_folder.cols) {
+            changes.push(Change::Resized { from: (old_folder.rows, old_folder.cols), to: (new_folder.rows, new_folder.cols) });
+        }
+        let fields = folder_fields(old_folder, new_folder);
+        if !fields.is_empty() {
+            changes.push(Change::FolderChanged(fields));
+        }
+        changes.extend(diff_buttons(
+            &old_folder.buttons, &new_folder.buttons,
+            &|index| Position::in_folder(old_folder, index),
+            &|index| Position::in_folder(new_folder, index),
+        ));
+        add(&old_folder.id, changes);
+    }
+
+    for new_folder in new.folders.iter().filter(|f| old.folder_index(&f.id).is_none()) {
+        let mut changes = vec![Change::FolderAdded];
+        changes.extend(diff_buttons(&[], &new_folder.buttons, &|_| unreachable!(), &|index| Position::in_folder(new_folder, index)));
+        add(&new_folder.id, changes);
+    }
+
+    add("hotbar", diff_buttons(
+        &old.hotbar.buttons, &new.hotbar.buttons,
+        &|index| Position::in_hotbar(old, index),
+        &|index| Position::in_hotbar(new, index),
+    ));
+
+    diffs
+}
+
+/// The result of a three-way merge. Conflicting buttons are marked with [`CONFLICT_MARKER`]
+/// and keep our version of everything but the label; anything else that conflicts keeps ours.
+pub struct Merged {
+    pub system: System,
+    pub conflicts: Vec<String>,
+}
+
+/// Whichever side changed `base`, or None if both did, differently.
+fn merge3<T: Clone + PartialEq>(base: &T, ours: &T, theirs: &T) -> Option<T> {
+    if ours == theirs || theirs == base {
+        Some(ours.clone())
+    } else if ours == base {
+        Some(theirs.clone())
+    } else {
+        None
+    }
+}
+
+fn merge_value<T: Clone + PartialEq>(base: &T, ours: &T, theirs: &T, place: &str, conflicts: &mut Vec<String>) -> T {
+    merge3(base, ours, theirs).unwrap_or_else(|| {
+        conflicts.push(format!("{}: changed on both sides", place));
+        ours.clone()
+    })
+}
+
+fn conflict_button(ours: Option<&Button>, theirs: Option<&Button>) -> Button {
+    fn label(button: Option<&Button>) -> &str {
+        button.map(|b| b.label.as_str()).unwrap_or("")
+    }
+
+    let mut button = ours.or(theirs).cloned().unwrap();
+    button.label = format!("{} {} ======= {} >>>>>>>", CONFLICT_MARKER, label(ours), label(theirs));
+    button
+}
+
+fn merge_cells(base: &[Option<Button>], ours: &[Option<Button>], theirs: &[Option<Button>], place: &str, conflicts: &mut Vec<String>) -> Vec<Option<Button>> {
+    let len = base.len().max(ours.len()).max(theirs.len());
+    let cell = |cells: &[Option<Button>], index: usize| cells.get(index).cloned().flatten();
+
+    let mut merged = vec![];
+    for index in 0..len {
+        let (b, o, t) = (cell(base, index), cell(ours, index), cell(theirs, index));
+        merged.push(merge3(&b, &o, &t).unwrap_or_else(|| {
+            conflicts.push(format!("{}: cell {} changed on both sides", place, index + 1));
+            Some(conflict_button(o.as_ref(), t.as_ref()))
+        }));
+    }
+
+    while merged.last().is_some_and(|b| b.is_none()) {
+        merged.pop();
+    }
+    merged
+}
+
+fn merge_folder(base: &Folder, ours: &Folder, theirs: &Folder, conflicts: &mut Vec<String>) -> Folder {
+    let place = format!("folder '{}'", ours.id);
+    let settings = |f: &Folder| (f.name.clone(), f.immediate, f.toplevel, f.rows, f.cols, f.background.clone(), f.border.clone(), f.category.clone());
+    let (name, immediate, toplevel, rows, cols, background, border, category) = merge_value(&settings(base), &settings(ours), &settings(theirs), &place, conflicts);
+    let buttons = merge_cells(&base.buttons, &ours.buttons, &theirs.buttons, &place, conflicts);
+
+    Folder { name, id: ours.id.clone(), immediate, toplevel, rows, cols, background, border, category, buttons }
+}
+
+fn merge_maps<V: Clone + PartialEq>(base: &HashMap<String, V>, ours: &HashMap<String, V>, theirs: &HashMap<String, V>, what: &str, conflicts: &mut Vec<String>) -> HashMap<String, V> {
+    let mut merged = HashMap::new();
+    let keys: BTreeSet<&String> = ours.keys().chain(theirs.keys()).chain(base.keys()).collect();
+    for key in keys {
+        let (b, o, t) = (base.get(key).cloned(), ours.get(key).cloned(), theirs.get(key).cloned());
+        let value = merge3(&b, &o, &t).unwrap_or_else(|| {
+            conflicts.push(format!("{} '{}': changed on both sides", what, key));
+            o.clone().or(t)
+        });
+        if let Some(value) = value {
+            merged.insert(key.clone(), value);
+        }
+    }
+    merged
+}
+
+/// Merges the changes from `base` to `theirs` into `ours`, like `git merge` but per button.
+pub fn merge(base: &System, ours: &System, theirs: &System) -> Merged {
+    let mut conflicts = vec![];
+    let find = |system: &System, id: &str| system.folder_index(id).map(|idx| system.folders[idx].clone());
+
+    // Our folders in our order, then any they added.
+    let mut ids: Vec<&String> = ours.folders.iter().map(|f| &f.id).collect();
+    for folder in &theirs.folders {
+        if !ids.contains(&&folder.id) && base.folder_index(&folder.id).is_none() {
+            ids.push(&folder.id);
+        }
+    }
+
+    let mut folders = vec![];
+    for id in ids {
+        let (b, o, t) = (find(base, id), find(ours, id), find(theirs, id));
+        let folder = match (merge3(&b, &o, &t), o, t) {
+            (Some(folder), _, _) => folder,
+            (None, Some(o), Some(t)) => Some(merge_folder(&b.unwrap_or_else(|| Folder { id: id.clone(), ..Default::default() }), &o, &t, &mut conflicts)),
+            (None, o, t) => {
+                conflicts.push(format!("folder '{}': deleted on one side, changed on the other", id));
+                o.or(t)
+            },
+        };
+        folders.extend(folder);
+    }
+
+    let hotbar_size = |s: &System| (s.hotbar.rows, s.hotbar.cols);
+    let (rows, cols) = merge_value(&hotbar_size(base), &hotbar_size(ours), &hotbar_size(theirs), "hotbar", &mut conflicts);
+    let mut hotbar = ours.hotbar.clone();
+    hotbar.rows = rows;
+    hotbar.cols = cols;
+    hotbar.buttons = merge_cells(&base.hotbar.buttons, &ours.hotbar.buttons, &theirs.hotbar.buttons, "hotbar", &mut conflicts);
+
+    let system = System {
+        name: merge_value(&base.name, &ours.name, &theirs.name, "name", &mut conflicts),
+        description: merge_value(&base.description, &ours.description, &theirs.description, "description", &mut conflicts),
+        folders,
+        hotbar,
+        variants: merge_maps(&base.variants, &ours.variants, &theirs.variants, "variants", &mut conflicts),
+        related: merge_maps(&base.related, &ours.related, &theirs.related, "related", &mut conflicts),
+        macros: merge_maps(&base.macros, &ours.macros, &theirs.macros, "macro", &mut conflicts),
+        color_scheme: merge_maps(&base.color_scheme, &ours.color_scheme, &theirs.color_scheme, "color scheme", &mut conflicts),
+        themes: merge_value(&base.themes, &ours.themes, &theirs.themes, "themes", &mut conflicts),
+        layout: merge_value(&base.layout, &ours.layout, &theirs.layout, "layout", &mut conflicts),
+    };
+
+    Merged { system, conflicts }
+}
+
+#[test]
+fn test_diff_and_merge() {
+    let base = crate::format::from_text("#sgs\nrows = 2\ncols = 2\n:Home\n\t\"yes\"\t\"no\"\n\t\"stop\"\t\"More\" -> Home::More\n.\n\
+        :Home::More\n\t\"a\"\t\"b\"\n.\n:hotbar\n\t\"hi\"\n.\n").unwrap();
+
+    assert!(diff(&base, &base).is_empty());
+
+    let mut ours = base.clone();
+    let home = &mut ours.folders[0];
+    home.buttons.swap(0, 1);
+    home.buttons[2].as_mut().unwrap().label = "halt".to_string();
+    home.buttons[3].as_mut().unwrap().pronunciation = Some("more".to_string());
+    ours.folders[1].cols = 3;
+    ours.folders[1].buttons.push(None);
+    ours.folders[1].buttons.push(Some(Button::new("c".to_string(), None, None)));
+    ours.hotbar.buttons.clear();
+
+    let lines: Vec<String> = diff(&base, &ours).iter().map(|d| d.to_string()).collect();
+    assert_eq!(vec![
+        "Home:\n    \
+            > 'yes' moved from page 1, row 1, column 1 to page 1, row 1, column 2\n    \
+            > 'no' moved from page 1, row 1, column 2 to page 1, row 1, column 1\n    \
+            ~ 'More' at page 1, row 2, column 2: pronunciation changed\n    \
+            ~ 'stop' relabeled 'halt' at page 1, row 2, column 1\n",
+        "Home::More:\n    ~ resized from 2x2 to 2x3\n    + 'c' at page 1, row 2, column 1\n",
+        "hotbar:\n    - 'hi' at page 1, row 1, column 1\n",
+    ], lines);
+
+    // They change a cell we didn't touch, and one we did.
+    let mut theirs = base.clone();
+    theirs.folders[0].buttons[1] = Some(Button::new("nope".to_string(), None, None));
+    theirs.folders[1].buttons[0].as_mut().unwrap().label = "A".to_string();
+    theirs.macros.insert("greet".to_string(), vec!["say: hello".to_string()]);
+
+    let merged = merge(&base, &ours, &theirs);
+    assert_eq!(vec!["folder 'Home': cell 2 changed on both sides".to_string()], merged.conflicts);
+    let home = &merged.system.folders[0];
+    assert_eq!(format!("{} yes ======= nope >>>>>>>", CONFLICT_MARKER), home.buttons[1].as_ref().unwrap().label);
+    assert_eq!("halt", home.buttons[2].as_ref().unwrap().label);
+    assert_eq!(3, merged.system.folders[1].cols);
+    assert_eq!("A", merged.system.folders[1].buttons[0].as_ref().unwrap().label);
+    assert!(merged.system.hotbar.buttons.is_empty());
+    assert_eq!(1, merged.system.macros.len());
+    assert_eq!(1, merged.system.problems().len());
+
+    // Without overlapping changes, merging either way gives the same System.
+    let mut theirs = base.clone();
+    theirs.folders.push(Folder { id: "Other".to_string(), toplevel: true, rows: 1, cols: 1, ..Default::default() });
+    let merged = merge(&base, &ours, &theirs);
+    assert!(merged.conflicts.is_empty());
+    assert_eq!(merged.system, merge(&base, &theirs, &ours).system);
+}
@@ -0,0 +1,201 @@
+use crate::button::Button;
+use crate::system::{Folder, System};
+
+use eframe::egui::Rect;
+
+/// State for the in-app System editor.
+#[derive(Debug, Default)]
+pub struct Editor {
+    /// Index (in the open folder) of the cell being edited.
+    pub selected: Option<usize>,
+    /// Cell being dragged, to swap with wherever it's dropped.
+    pub dragging: Option<usize>,
+    /// Where each cell was drawn this frame, for working out drops.
+    pub cells: Vec<(usize, Rect)>,
+    /// The folder `rows`/`cols` were taken from.
+    pub folder: usize,
+    pub rows: usize,
+    pub cols: usize,
+    pub new_folder_name: String,
+    /// Where "Save" writes to.
+    pub path: String,
+    /// Changed since the last save.
+    pub dirty: bool,
+    pub message: Option<String>,
+}
+
+impl Editor {
+    pub fn new(system: &System, folder: usize, path: Option<String>) -> Self {
+        let mut editor = Self {
+            path: path.unwrap_or_else(|| "system.json".to_string()),
+            ..Default::default()
+        };
+        editor.open(system, folder);
+        editor
+    }
+
+    /// Resets anything specific to the previous folder.
+    pub fn open(&mut self, system: &System, folder: usize) {
+        self.folder = folder;
+        self.selected = None;
+        self.dragging = None;
+        if let Some(f) = system.folders.get(folder) {
+            self.rows = f.rows;
+            self.cols = f.cols;
+        }
+    }
+}
+
+/// Puts `button` at `index`, growing the folder if needed.
+pub fn set_button(folder: &mut Folder, index: usize, button: Option<Button>) {
+    if index >= folder.buttons.len() {
+        if button.is_none() {
+            return;
+        }
+        folder.buttons.resize(index + 1, None);
+    }
+    folder.buttons[index] = button;
+
+    // Trailing empty cells would only add empty pages.
+    while folder.buttons.last().is_some_and(|b| b.is_none()) {
+        folder.buttons.pop();
+    }
+}
+
+pub fn swap_buttons(folder: &mut Folder, a: usize, b: usize) {
+    let button_a = folder.buttons.get(a).cloned().flatten();
+    let button_b = folder.buttons.get(b).cloned().flatten();
+    set_button(folder, a, button_b);
+    set_button(folder, b, button_a);
+}
+
+/// Changes the grid size, keeping buttons on the first page at the same row and column
+/// where they still fit. Anything else moves to the end.
+pub fn resize_folder(folder: &mut Folder, rows: usize, cols: usize) {
+    let rows = rows.max(1);
+    let cols = cols.max(1);
+
+    let mut placed: Vec<Option<Button>> = vec![];
+    let mut overflow = vec![];
+    for (index, button) in folder.buttons.iter().enumerate() {
+        let Some(button) = button else { continue };
+        let (page, col, row) = folder.position_of(index);
+        if page == 0 && col < cols && row < rows {
+            let new_index = row * cols + col;
+            if new_index >= placed.len() {
+                placed.resize(new_index + 1, None);
+            }
+            placed[new_index] = Some(button.clone());
+        } else {
+            overflow.push(Some(button.clone()));
+        }
+    }
+    placed.extend(overflow);
+
+    folder.rows = rows;
+    folder.cols = cols;
+    folder.buttons = placed;
+}
+
+/// Adds an empty folder. A sub-folder gets a link in the first free cell of its parent.
+/// Returns the new folder's index.
+pub fn add_folder(system: &mut System, name: &str, parent: Option<usize>) -> Result<usize, String> {
+    let name = name.trim();
+    if name.is_empty() || name.contains("::") {
+        return Err(format!("'{}' can't be used as a folder name.", name));
+    }
+
+    let (id, rows, cols) = match parent.and_then(|idx| system.folders.get(idx)) {
+        Some(parent) => (format!("{}::{}", parent.id, name), parent.rows, parent.cols),
+        None => (name.to_string(), system.grid_size().0, system.grid_size().1),
+    };
+    if system.folder_index(&id).is_some() {
+        return Err(format!("There's already a folder called '{}'.", id));
+    }
+
+    system.folders.push(Folder {
+        name: name.to_string(),
+        id: id.clone(),
+        toplevel: parent.is_none(),
+        rows,
+        cols,
+        ..Default::default()
+    });
+
+    if let Some(parent) = parent.and_then(|idx| system.folders.get_mut(idx)) {
+        let mut link = Button::new(name.to_string(), None, None);
+        link.folder = Some(id);
+        link.parent = Some(parent.id.clone());
+        let free = parent.buttons.iter().position(|b| b.is_none()).unwrap_or(parent.buttons.len());
+        set_button(parent, free, Some(link));
+    }
+
+    Ok(system.folders.len() - 1)
+}
+
+/// Deletes a folder and its sub-folders. Links to them are cleared, leaving empty cells,
+/// so nothing else moves.
+pub fn delete_folder(system: &mut System, folder: usize) -> Result<(), String> {
+    let Some(id) = system.folders.get(folder).map(|f| f.id.clone()) else {
+        return Err("No such folder.".to_string());
+    };
+    if system.folders[folder].toplevel && system.toplevel_folders().len() == 1 {
+        return Err("The last top-level folder can't be deleted.".to_string());
+    }
+
+    let prefix = format!("{}::", id);
+    let deleted = |other: &str| other == id || other.starts_with(&prefix);
+
+    system.folders.retain(|f| !deleted(&f.id));
+    for f in system.folders.iter_mut() {
+        for cell in f.buttons.iter_mut() {
+            if cell.as_ref().and_then(|b| b.folder.as_deref()).is_some_and(deleted) {
+                *cell = None;
+            }
+        }
+        while f.buttons.last().is_some_and(|b| b.is_none()) {
+            f.buttons.pop();
+        }
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_editor() {
+    let button = |label: &str| Some(Button::new(label.to_string(), None, None));
+    let mut system = System::default();
+    system.folders.push(Folder {
+        name: "Home".to_string(), id: "Home".to_string(), toplevel: true, rows: 2, cols: 3,
+        buttons: vec![button("a"), button("b"), None, button("d")],
+        ..Default::default()
+    });
+
+    let home = &mut system.folders[0];
+    swap_buttons(home, 0, 2);
+    assert_eq!(vec![None, button("b"), button("a"), button("d")], home.buttons);
+    swap_buttons(home, 2, 5);
+    assert_eq!(vec![None, button("b"), None, button("d"), None, button("a")], home.buttons);
+    set_button(home, 5, None);
+    assert_eq!(4, home.buttons.len());
+
+    // "d" is at row 1, column 0 and stays there; "b" no longer fits.
+    resize_folder(home, 3, 1);
+    assert_eq!(vec![None, button("d"), button("b")], home.buttons);
+
+    let animals = add_folder(&mut system, "Animals", Some(0)).unwrap();
+    assert_eq!("Home::Animals", system.folders[animals].id);
+    assert_eq!(Some("Home::Animals".to_string()), system.folders[0].buttons[0].as_ref().unwrap().folder);
+    assert!(add_folder(&mut system, "Animals", Some(0)).is_err());
+    add_folder(&mut system, "Pets", Some(animals)).unwrap();
+    add_folder(&mut system, "Other", None).unwrap();
+    assert_eq!(4, system.folders.len());
+
+    delete_folder(&mut system, animals).unwrap();
+    assert_eq!(vec!["Home", "Other"], system.folders.iter().map(|f| f.id.as_str()).collect::<Vec<_>>());
+    assert_eq!(None, system.folders[0].buttons[0]);
+    assert_eq!(button("d"), system.folders[0].buttons[1]);
+
+    delete_folder(&mut system, 1).unwrap();
+    assert!(delete_folder(&mut system, 0).is_err());
+}
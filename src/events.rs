@@ -0,0 +1,79 @@
+//! Things that happen in the app, for a page embedding it to listen for.
+
+use nanoserde::SerJson;
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    /// A word (or action) button was pressed. `folder` is None for the hotbar.
+    ButtonPressed { label: String, folder: Option<String> },
+    Spoken(String),
+    /// The board switched to the folder with this id.
+    FolderChanged(String),
+}
+
+pub type Listener = Box<dyn FnMut(&Event)>;
+
+impl Event {
+    pub const NAMES: [&'static str; 3] = ["button", "spoken", "folder"];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::ButtonPressed { .. } => "button",
+            Self::Spoken(_) => "spoken",
+            Self::FolderChanged(_) => "folder",
+        }
+    }
+
+    /// The details, as a JSON object.
+    pub fn to_json(&self) -> String {
+        match self {
+            Self::ButtonPressed { label, folder } => format!("{{\"label\":{},\"folder\":{}}}", label.serialize_json(), folder.serialize_json()),
+            Self::Spoken(text) => format!("{{\"text\":{}}}", text.serialize_json()),
+            Self::FolderChanged(id) => format!("{{\"folder\":{}}}", id.serialize_json()),
+        }
+    }
+}
+
+#[test]
+fn test_event_json() {
+    let pressed = Event::ButtonPressed { label: "say \"hi\"".to_string(), folder: None };
+    assert_eq!("button", pressed.name());
+    assert_eq!(r#"{"label":"say \"hi\"","folder":null}"#, pressed.to_json());
+    assert_eq!(r#"{"folder":"Home::More"}"#, Event::FolderChanged("Home::More".to_string()).to_json());
+    assert!(Event::NAMES.contains(&Event::Spoken(String::new()).name()));
+}
+
+#[test]
+fn test_app_events() {
+    use crate::action::Action;
+    use crate::app::App;
+    use crate::settings::Settings;
+    use eframe::egui;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    let system = crate::format::from_text("#sgs\nrows = 1\ncols = 2\n:Home\n\t\"hi\"\t\"More\" -> Home::More\n.\n:Home::More\n\t\"bye\"\n.\n").unwrap();
+    let ctx = egui::Context::default();
+    let mut app = App::headless(&ctx, system, Settings::default());
+    let seen = Rc::new(RefCell::new(vec![]));
+    app.on_event({
+        let seen = seen.clone();
+        Box::new(move |event| seen.borrow_mut().push(event.clone()))
+    });
+    let frame = |app: &mut App| { let _ = ctx.run(egui::RawInput::default(), |ctx| app.draw(ctx)); };
+
+    // Listeners hear where the board starts.
+    frame(&mut app);
+    assert_eq!(vec![Event::FolderChanged("Home".to_string())], *seen.borrow());
+
+    app.set_panel_text("  i want ");
+    assert_eq!("i want", app.panel_text());
+    assert!(!app.open_folder("Nope"));
+    assert!(app.open_folder("Home::More"));
+    app.perform(&Action::Speak);
+    // Nothing's passed on until the end of the frame.
+    assert_eq!(1, seen.borrow().len());
+
+    frame(&mut app);
+    assert_eq!(vec![Event::Spoken("i want".to_string()), Event::FolderChanged("Home::More".to_string())], seen.borrow()[1..]);
+}
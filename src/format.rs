@@ -0,0 +1,315 @@
+//! The "#sgs" text format (see notes/system.txt), for people who'd rather edit a
+//! System in a text editor than as JSON.
+//!
+//! A folder starts with `:Folder::Id (append)` (or `immediate`), has one line
+//! per row, and ends with a line holding just `.`.
+//!
+//! Cells are separated by tabs. `-` is an empty cell, `"word" = "say this"` sets a
+//! pronunciation, and `"label" -> Folder::Id` links to a folder. `rows` and `cols`
+//! apply to every folder after them. `:hotbar` holds the hotbar.
+//!
+//! It only covers folders and buttons; everything else is lost when converting to it.
+
+use crate::button::Button;
+use crate::system::{Folder, System};
+
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Json,
+    Text,
+}
+
+impl Format {
+    /// Guesses from the file extension, defaulting to JSON.
+    pub fn from_path(path: &str) -> Self {
+        match Path::new(path).extension().and_then(|e| e.to_str()) {
+            Some("sgs") | Some("txt") => Self::Text,
+            _ => Self::Json,
+        }
+    }
+
+    pub fn detect(contents: &str) -> Self {
+        if contents.trim_start().starts_with("#sgs") {
+            Self::Text
+        } else {
+            Self::Json
+        }
+    }
+}
+
+fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Reads a quoted string from the start of `s`, returning it and whatever follows.
+fn unquote(s: &str) -> Result<(String, &str), String> {
+    let Some(rest) = s.strip_prefix('"') else {
+        return Err(format!("expected a quoted string, got '{}'", s));
+    };
+
+    let mut result = String::new();
+    let mut chars = rest.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok((result, &rest[i + 1..])),
+            '\\' => match chars.next() {
+                Some((_, c)) => result.push(c),
+                None => break,
+            },
+            c => result.push(c),
+        }
+    }
+
+    Err(format!("unterminated string: {}", s))
+}
+
+fn parse_cell(cell: &str, folder_id: Option<&str>) -> Result<Option<Button>, String> {
+    let cell = cell.trim();
+    if cell == "-" {
+        return Ok(None);
+    }
+
+    let (label, rest) = unquote(cell)?;
+    let mut button = Button::new(label, None, None);
+    button.parent = folder_id.map(|id| id.to_string());
+
+    let mut rest = rest.trim();
+    if let Some(pronunciation) = rest.strip_prefix('=') {
+        let (pronunciation, after) = unquote(pronunciation.trim_start())?;
+        button.pronunciation = Some(pronunciation);
+        rest = after.trim();
+    }
+    if let Some(folder) = rest.strip_prefix("->") {
+        button.folder = Some(folder.trim().to_string());
+        rest = "";
+    }
+    if !rest.is_empty() {
+        return Err(format!("unexpected '{}' after {}", rest, quote(&button.label)));
+    }
+
+    Ok(Some(button))
+}
+
+fn write_cell(button: &Option<Button>) -> String {
+    let Some(button) = button else { return "-".to_string() };
+
+    let mut cell = quote(&button.label);
+    if let Some(pronunciation) = &button.pronunciation {
+        cell.push_str(&format!(" = {}", quote(pronunciation)));
+    }
+    if let Some(folder) = &button.folder {
+        cell.push_str(&format!(" -> {}", folder));
+    }
+
+    cell
+}
+
+pub fn from_text(text: &str) -> Result<System, String> {
+    let mut system = System::default();
+    let (mut rows, mut cols) = (0, 0);
+    // The folder being read, or None for the hotbar.
+    let mut current: Option<Option<Folder>> = None;
+    let mut buttons: Vec<Option<Button>> = vec![];
+
+    for (n, line) in text.lines().enumerate() {
+        let error = |message: String| format!("line {}: {}", n + 1, message);
+
+        if n == 0 {
+            if line.trim() != "#sgs" {
+                return Err(error("the first line must be '#sgs'".to_string()));
+            }
+            continue;
+        }
+
+        if let Some(folder) = &current {
+            if line.trim() == "." {
+                match folder.clone() {
+                    Some(mut folder) => {
+                        while buttons.last().is_some_and(|b| b.is_none()) {
+                            buttons.pop();
+                        }
+                        folder.buttons = std::mem::take(&mut buttons);
+                        system.folders.push(folder);
+                    },
+                    None => {
+                        system.hotbar.rows = rows;
+                        system.hotbar.cols = cols;
+                        system.hotbar.buttons = std::mem::take(&mut buttons);
+                    },
+                }
+                current = None;
+            } else if !line.trim().is_empty() {
+                let folder_id = folder.as_ref().map(|f| f.id.as_str());
+                let mut row = line.trim_start_matches('\t').split('\t')
+                    .map(|cell| parse_cell(cell, folder_id).map_err(error))
+                    .collect::<Result<Vec<_>, _>>()?;
+                if row.len() > cols {
+                    return Err(error(format!("{} cells, but cols = {}", row.len(), cols)));
+                }
+                row.resize(cols, None);
+                buttons.extend(row);
+            }
+            continue;
+        }
+
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(header) = line.strip_prefix(':') {
+            if rows == 0 || cols == 0 {
+                return Err(error("set rows and cols before the first folder".to_string()));
+            }
+            if header.trim() == "hotbar" {
+                current = Some(None);
+                continue;
+            }
+
+            let (id, flags) = match header.split_once('(') {
+                Some((id, flags)) => (id.trim(), flags.trim_end_matches(')').split(',').map(|f| f.trim()).collect()),
+                None => (header.trim(), vec![]),
+            };
+            let mut folder = Folder {
+                name: id.rsplit("::").next().unwrap_or(id).to_string(),
+                id: id.to_string(),
+                toplevel: !id.contains("::"),
+                rows,
+                cols,
+                ..Default::default()
+            };
+            for flag in flags {
+                match flag {
+                    "append" => folder.immediate = false,
+                    "immediate" => folder.immediate = true,
+                    "hidden" => folder.toplevel = false,
+                    "toplevel" => folder.toplevel = true,
+                    _ => return Err(error(format!("unknown folder option '{}'", flag))),
+                }
+            }
+            current = Some(Some(folder));
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(error(format!("expected 'key = value', got '{}'", line)));
+        };
+        let value = value.trim();
+        match key.trim() {
+            "name" => system.name = unquote(value).map_err(error)?.0,
+            "description" => system.description = unquote(value).map_err(error)?.0,
+            "rows" => rows = value.parse().map_err(|_| error(format!("invalid rows '{}'", value)))?,
+            "cols" => cols = value.parse().map_err(|_| error(format!("invalid cols '{}'", value)))?,
+            key => return Err(error(format!("unknown setting '{}'", key))),
+        }
+    }
+
+    if current.is_some() {
+        return Err("the last folder is missing its closing '.'".to_string());
+    }
+
+    Ok(system)
+}
+
+/// Writes `system` in the text format. Also returns a description of anything the
+/// text format can't hold, which is left out.
+pub fn to_text(system: &System) -> (String, Vec<String>) {
+    let mut lost = vec![];
+    let mut out = String::from("#sgs\n\n");
+    out.push_str(&format!("name = {}\n", quote(&system.name)));
+    out.push_str(&format!("description = {}\n", quote(&system.description)));
+
+    let (mut rows, mut cols) = (0, 0);
+    let mut section = |out: &mut String, header: &str, r: usize, c: usize, buttons: &[Option<Button>]| {
+        out.push('\n');
+        if (r, c) != (rows, cols) {
+            out.push_str(&format!("rows = {}\ncols = {}\n\n", r, c));
+            (rows, cols) = (r, c);
+        }
+        out.push_str(&format!(":{}\n", header));
+        for row in buttons.chunks(c.max(1)) {
+            let cells: Vec<String> = row.iter().map(write_cell).collect();
+            out.push_str(&format!("\t{}\n", cells.join("\t")));
+        }
+        out.push_str(".\n");
+    };
+
+    for folder in &system.folders {
+        let mut flags = vec![if folder.immediate { "immediate" } else { "append" }];
+        if folder.toplevel && folder.id.contains("::") {
+            flags.push("toplevel");
+        } else if !folder.toplevel && !folder.id.contains("::") {
+            flags.push("hidden");
+        }
+        section(&mut out, &format!("{} ({})", folder.id, flags.join(", ")), folder.rows, folder.cols, &folder.buttons);
+
+        if folder.name != folder.id.rsplit("::").next().unwrap_or(&folder.id) {
+            lost.push(format!("name of folder '{}'", folder.id));
+        }
+        if folder.background.is_some() || folder.border.is_some() || folder.category.is_some() {
+            lost.push(format!("colors of folder '{}'", folder.id));
+        }
+    }
+    // An empty hotbar has no size to write, and reads back the same without a section.
+    if !system.hotbar.buttons.is_empty() {
+        section(&mut out, "hotbar", system.hotbar.rows, system.hotbar.cols, &system.hotbar.buttons);
+    }
+
+    let buttons = system.folders.iter().flat_map(|f| f.buttons.iter()).chain(system.hotbar.buttons.iter()).flatten();
+    let mut count = |what: &str, n: usize| {
+        if n > 0 {
+            lost.push(format!("{}: {}", what, n));
+        }
+    };
+    count("button actions", buttons.clone().filter(|b| b.action.is_some()).count());
+    count("button images", buttons.clone().filter(|b| b.image.is_some()).count());
+    count("button ids", buttons.clone().filter(|b| b.id.is_some()).count());
+    count("hidden buttons", buttons.clone().filter(|b| b.hidden).count());
+    count("button colors", buttons.filter(|b| b.background.is_some() || b.border.is_some() || b.category.is_some()).count());
+    count("variant lists", system.variants.len());
+    count("related word lists", system.related.len());
+    count("macros", system.macros.len());
+    count("color scheme entries", system.color_scheme.len());
+    count("themes", system.themes.len());
+    count("layout", system.layout.iter().count());
+
+    (out, lost)
+}
+
+#[test]
+fn test_text_format() {
+    let text = "#sgs\n\nname = \"Example \\\"system\\\".\"\ndescription = \"\"\n\nrows = 2\ncols = 3\n\n\
+        :Home (append)\n\t\"1\"\t\"2\"\t\"3\"\n\t\"4\" = \"four\"\t-\t\"Animals\" -> Home::Animals\n.\n\n\
+        rows = 1\ncols = 2\n\n:Home::Animals (immediate)\n\t\"cat\"\n.\n\n:hotbar\n\t\"yes\"\t\"no\"\n.\n";
+
+    let system = from_text(text).unwrap();
+    assert_eq!("Example \"system\".", system.name);
+    assert_eq!(2, system.folders.len());
+    let home = &system.folders[0];
+    assert!(home.toplevel && !home.immediate);
+    assert_eq!((2, 3, 6), (home.rows, home.cols, home.buttons.len()));
+    assert_eq!(Some("four".to_string()), home.buttons[3].as_ref().unwrap().pronunciation);
+    assert_eq!(None, home.buttons[4]);
+    assert_eq!(Some("Home::Animals".to_string()), home.buttons[5].as_ref().unwrap().folder);
+    let animals = &system.folders[1];
+    assert_eq!(("Animals", false, true), (animals.name.as_str(), animals.toplevel, animals.immediate));
+    let mut cat = Button::new("cat".to_string(), None, None);
+    cat.parent = Some("Home::Animals".to_string());
+    assert_eq!(vec![Some(cat)], animals.buttons);
+    assert_eq!(2, system.hotbar.buttons.len());
+
+    let (written, lost) = to_text(&system);
+    assert!(lost.is_empty());
+    assert_eq!(text, written);
+    assert_eq!(Format::Text, Format::detect(&written));
+
+    let mut system = system;
+    system.hotbar = Default::default();
+    assert_eq!(system, from_text(&to_text(&system).0).unwrap());
+
+    assert!(from_text("#sgs\n:Home\n.\n").unwrap_err().starts_with("line 2:"));
+    assert!(from_text("#sgs\nrows = 1\ncols = 1\n:Home\n\t\"a\"\t\"b\"\n.\n").is_err());
+    assert!(from_text("#sgs\nrows = 1\ncols = 1\n:Home\n\t\"a\n.\n").is_err());
+}
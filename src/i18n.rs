@@ -0,0 +1,250 @@
+//! Translations of the app's own words (not the System's), and right-to-left languages.
+//!
+//! Messages are looked up by their English text, so anything a catalog doesn't
+//! translate stays in English. `{}` in a message is filled in, in order.
+
+use log::warn;
+use nanoserde::DeJson;
+
+use std::collections::HashMap;
+
+#[derive(Clone, Debug, DeJson, PartialEq)]
+pub struct Catalog {
+    /// e.g. "es".
+    pub locale: String,
+    /// The language's name, in that language, e.g. "Español".
+    pub name: String,
+    /// Written right to left, e.g. Arabic. Mirrors the screen and the board.
+    #[nserde(default)]
+    pub rtl: bool,
+    #[nserde(default)]
+    pub messages: HashMap<String, String>,
+}
+
+impl Default for Catalog {
+    fn default() -> Self {
+        Self { locale: "en".to_string(), name: "English".to_string(), rtl: false, messages: HashMap::new() }
+    }
+}
+
+impl Catalog {
+    pub fn load_str(json: &str) -> Result<Self, String> {
+        let catalog: Self = DeJson::deserialize_json(json).map_err(|err| err.to_string())?;
+        for (message, translation) in &catalog.messages {
+            if message.matches("{}").count() != translation.matches("{}").count() {
+                return Err(format!("{}: the translation of {:?} has a different number of {{}}s", catalog.locale, message));
+            }
+        }
+        Ok(catalog)
+    }
+
+    pub fn load_file(path: &str) -> Result<Self, String> {
+        let json = std::fs::read_to_string(path).map_err(|err| format!("{}: {}", path, err))?;
+        Self::load_str(&json).map_err(|err| format!("{}: {}", path, err))
+    }
+
+    /// `message` in this language, ready to show.
+    pub fn t(&self, message: &str) -> String {
+        display(self.messages.get(message).map(String::as_str).unwrap_or(message))
+    }
+
+    /// Like `t`, with each `{}` replaced by the next of `args`.
+    pub fn fill(&self, message: &str, args: &[&str]) -> String {
+        let template = self.messages.get(message).map(String::as_str).unwrap_or(message);
+        let mut parts = template.split("{}");
+        let mut out = parts.next().unwrap_or_default().to_string();
+        for (part, arg) in parts.zip(args.iter().chain(std::iter::repeat(&""))) {
+            out += arg;
+            out += part;
+        }
+        display(&out)
+    }
+}
+
+/// English, and the translations that come with SGS.
+pub fn bundled() -> Vec<Catalog> {
+    let mut catalogs = vec![Catalog::default()];
+    for json in [include_str!("../locales/es.json"), include_str!("../locales/ar.json")] {
+        catalogs.push(Catalog::load_str(json).expect("Failed to load bundled translations. (This should never happen.)"));
+    }
+    catalogs
+}
+
+/// The catalog for `locale` from `catalogs`, or English.
+pub fn find(locale: &str, catalogs: &[Catalog]) -> Catalog {
+    // Later catalogs, e.g. loaded from files, replace bundled ones.
+    catalogs.iter().rev().find(|c| c.locale == locale).cloned().unwrap_or_else(|| {
+        warn!("No translations for '{}'; using English.", locale);
+        Catalog::default()
+    })
+}
+
+fn is_rtl(c: char) -> bool {
+    matches!(c, '\u{0590}'..='\u{08FF}' | '\u{FB1D}'..='\u{FDFF}' | '\u{FE70}'..='\u{FEFF}')
+}
+
+// Arabic letters that change shape depending on their neighbors: (letter, its isolated
+// form in Arabic Presentation Forms-B, whether it also joins to the letter after it).
+// The final, initial and medial forms follow the isolated one.
+const ARABIC_FORMS: [(char, u32, bool); 36] = [
+    ('\u{0621}', 0xFE80, false), ('\u{0622}', 0xFE81, false), ('\u{0623}', 0xFE83, false),
+    ('\u{0624}', 0xFE85, false), ('\u{0625}', 0xFE87, false), ('\u{0626}', 0xFE89, true),
+    ('\u{0627}', 0xFE8D, false), ('\u{0628}', 0xFE8F, true), ('\u{0629}', 0xFE93, false),
+    ('\u{062A}', 0xFE95, true), ('\u{062B}', 0xFE99, true), ('\u{062C}', 0xFE9D, true),
+    ('\u{062D}', 0xFEA1, true), ('\u{062E}', 0xFEA5, true), ('\u{062F}', 0xFEA9, false),
+    ('\u{0630}', 0xFEAB, false), ('\u{0631}', 0xFEAD, false), ('\u{0632}', 0xFEAF, false),
+    ('\u{0633}', 0xFEB1, true), ('\u{0634}', 0xFEB5, true), ('\u{0635}', 0xFEB9, true),
+    ('\u{0636}', 0xFEBD, true), ('\u{0637}', 0xFEC1, true), ('\u{0638}', 0xFEC5, true),
+    ('\u{0639}', 0xFEC9, true), ('\u{063A}', 0xFECD, true), ('\u{0641}', 0xFED1, true),
+    ('\u{0642}', 0xFED5, true), ('\u{0643}', 0xFED9, true), ('\u{0644}', 0xFEDD, true),
+    ('\u{0645}', 0xFEE1, true), ('\u{0646}', 0xFEE5, true), ('\u{0647}', 0xFEE9, true),
+    ('\u{0648}', 0xFEED, false), ('\u{0649}', 0xFEEF, false), ('\u{064A}', 0xFEF1, true),
+];
+
+const TATWEEL: char = '\u{0640}';
+
+// Lam followed by one of these alefs is written as a single ligature: (alef, isolated form).
+const LAM_ALEF: [(char, u32); 4] = [('\u{0622}', 0xFEF5), ('\u{0623}', 0xFEF7), ('\u{0625}', 0xFEF9), ('\u{0627}', 0xFEFB)];
+
+fn forms(c: char) -> Option<(u32, bool)> {
+    ARABIC_FORMS.iter().find(|(letter, _, _)| *letter == c).map(|(_, isolated, dual)| (*isolated, *dual))
+}
+
+// Vowel marks and the like sit on a letter without affecting how it joins.
+fn is_transparent(c: char) -> bool {
+    matches!(c, '\u{064B}'..='\u{065F}' | '\u{0670}')
+}
+
+fn joins_next(c: char) -> bool {
+    c == TATWEEL || forms(c).is_some_and(|(_, dual)| dual)
+}
+
+fn joins_previous(c: char) -> bool {
+    c == TATWEEL || (forms(c).is_some() && c != '\u{0621}')
+}
+
+/// Picks each Arabic letter's joined form, since egui doesn't.
+pub fn shape(text: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let letter_before = |i: usize| chars[..i].iter().rev().find(|c| !is_transparent(**c)).copied();
+    let letter_after = |i: usize| chars[i + 1..].iter().find(|c| !is_transparent(**c)).copied();
+
+    let mut out = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        let Some((isolated, dual)) = forms(c) else {
+            out.push(c);
+            i += 1;
+            continue;
+        };
+        let after_joined = letter_before(i).is_some_and(joins_next) && joins_previous(c);
+
+        let alef = chars.get(i + 1).and_then(|next| LAM_ALEF.iter().find(|(alef, _)| alef == next));
+        if let (true, Some((_, ligature))) = (c == '\u{0644}', alef) {
+            out.extend(char::from_u32(ligature + after_joined as u32));
+            i += 2;
+            continue;
+        }
+
+        let before_joined = dual && letter_after(i).is_some_and(joins_previous);
+        let form = match (after_joined, before_joined) {
+            (false, false) => 0,
+            (true, false) => 1,
+            (false, true) => 2,
+            (true, true) => 3,
+        };
+        out.extend(char::from_u32(isolated + form));
+        i += 1;
+    }
+    out
+}
+
+fn is_digit(c: char) -> bool {
+    c.is_ascii_digit() || matches!(c, '\u{0660}'..='\u{0669}' | '\u{06F0}'..='\u{06F9}')
+}
+
+// Marks drawn on the letter before them, e.g. Arabic vowel marks.
+fn is_mark(c: char) -> bool {
+    is_transparent(c) || matches!(c, '\u{0300}'..='\u{036F}' | '\u{0610}'..='\u{061A}' | '\u{06D6}'..='\u{06ED}')
+}
+
+/// A right-to-left word, reversed so it reads right when drawn left to right. Each letter keeps
+/// its marks after it, and numbers, which are written left to right even in Arabic, stay as they are.
+fn reverse_word(word: &str) -> String {
+    let chars: Vec<char> = word.chars().collect();
+    let mut pieces: Vec<&[char]> = vec![];
+    let mut start = 0;
+    while start < chars.len() {
+        let mut end = start + 1;
+        if is_digit(chars[start]) {
+            // e.g. "3.5" or "1,000".
+            while end < chars.len() && (is_digit(chars[end]) || (matches!(chars[end], '.' | ',' | '\u{066B}' | '\u{066C}') && chars.get(end + 1).is_some_and(|c| is_digit(*c)))) {
+                end += 1;
+            }
+        }
+        while end < chars.len() && is_mark(chars[end]) {
+            end += 1;
+        }
+        pieces.push(&chars[start..end]);
+        start = end;
+    }
+    pieces.iter().rev().flat_map(|piece| piece.iter()).collect()
+}
+
+/// `text` as it should be drawn. egui lays out everything left to right, so right-to-left
+/// words are shaped and reversed, and so is the order of the words on each line.
+/// Anything without right-to-left letters is left alone.
+pub fn display(text: &str) -> String {
+    if !text.chars().any(is_rtl) {
+        return text.to_string();
+    }
+    let lines: Vec<String> = text.lines().map(|line| {
+        let words: Vec<String> = shape(line).split(' ')
+            .map(|word| if word.chars().any(is_rtl) { reverse_word(word) } else { word.to_string() })
+            .rev()
+            .collect();
+        words.join(" ")
+    }).collect();
+    lines.join("\n")
+}
+
+/// The Arabic letters from DejaVu Sans, which egui's fonts don't have. See fonts/.
+pub const ARABIC_FONT: &[u8] = include_bytes!("../fonts/DejaVuSans-Arabic.ttf");
+
+#[test]
+fn test_catalog() {
+    let catalogs = bundled();
+    let spanish = find("es", &catalogs);
+    assert_eq!("Hablar", spanish.t("Speak"));
+    assert_eq!("Not translated", spanish.t("Not translated"));
+    assert_eq!("1/3 ->", find("en", &catalogs).fill("{}/{} ->", &["1", "3"]));
+    assert_eq!("English", find("xx", &catalogs).name);
+
+    let arabic = find("ar", &catalogs);
+    assert!(arabic.rtl);
+    // Both messages are filled in, and the arrow points the way the board reads.
+    assert_eq!("1/3 <-", arabic.fill("{}/{} ->", &["1", "3"]));
+    // Every message the Spanish translates, the Arabic does too.
+    assert_eq!(None, spanish.messages.keys().find(|message| !arabic.messages.contains_key(*message)));
+
+    assert!(Catalog::load_str(r#"{"locale": "x", "name": "X", "messages": {"{} of {}": "{}"}}"#).is_err());
+}
+
+#[test]
+fn test_arabic_display() {
+    // "salam": seen initial, lam-alef ligature (joined), meem on its own.
+    assert_eq!("\u{FEB3}\u{FEFC}\u{FEE1}", shape("سلام"));
+    // Alef doesn't join to what follows, so the beh after it starts afresh.
+    assert_eq!("\u{FE8D}\u{FE91}\u{FEE6}", shape("ابن"));
+    // Vowel marks don't break joins.
+    assert_eq!("\u{FE91}\u{064E}\u{FE8E}", shape("بَا"));
+
+    assert_eq!("\u{FEE1}\u{FEFC}\u{FEB3} 2", display("2 سلام"));
+    assert_eq!("Speak", display("Speak"));
+    // "baba" with a fatha on each beh: the marks stay after their letters.
+    assert_eq!("\u{FE8E}\u{FE91}\u{064E}\u{FE8E}\u{FE91}\u{064E}", display("بَابَا"));
+    // Numbers read left to right, even written in Arabic digits.
+    assert_eq!("١٢ \u{FE8F}\u{FE8E}\u{FE98}\u{FEDB}", display("كتاب ١٢"));
+    assert_eq!("٣٫٥\u{FEDD}", display("ل٣٫٥"));
+}
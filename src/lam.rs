@@ -0,0 +1,376 @@
+//! Language activity monitoring: an opt-in log of what was pressed and said, for
+//! speech-language pathologists to measure progress from. It stays on the device,
+//! and can be exported in the LAM text format or as CSV.
+
+use log::info;
+use nanoserde::{DeJson, DeJsonErr, SerJson};
+
+/// Where a button was pressed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Source {
+    Board,
+    Hotbar,
+    Related,
+    Variants,
+    Keyboard,
+    Search,
+}
+
+impl Source {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Board => "board",
+            Self::Hotbar => "hotbar",
+            Self::Related => "related",
+            Self::Variants => "variants",
+            Self::Keyboard => "keyboard",
+            Self::Search => "search",
+        }
+    }
+
+    // LAM's mnemonics for how a word was made: spelled, or picked from a single-meaning
+    // picture (any button).
+    // TODO: "WPR" for predicted words, once SGS has word prediction.
+    fn mnemonic(name: &str) -> &'static str {
+        match name {
+            "keyboard" => "SPE",
+            _ => "SMP",
+        }
+    }
+}
+
+#[derive(Clone, Debug, DeJson, SerJson, PartialEq)]
+pub struct Record {
+    /// Milliseconds since 1970-01-01, UTC.
+    pub time: u64,
+    /// A `Source` name for button presses, or "spoken" for utterances.
+    pub source: String,
+    #[nserde(default)]
+    pub folder: Option<String>,
+    #[nserde(default)]
+    pub page: Option<usize>,
+    /// Index of the button in its folder, or on the hotbar page or list it came from.
+    #[nserde(default)]
+    pub cell: Option<usize>,
+    /// The button's label, or what was spoken.
+    pub text: String,
+}
+
+pub const REDACTED: &str = "[redacted]";
+
+#[derive(Clone, Debug, Default, DeJson, SerJson, PartialEq)]
+pub struct Lam {
+    /// Nothing is logged unless this is turned on.
+    #[nserde(default)]
+    pub enabled: bool,
+    #[nserde(default)]
+    pub paused: bool,
+    #[nserde(default)]
+    pub records: Vec<Record>,
+}
+
+impl Lam {
+    pub const STORAGE_KEY: &'static str = "lam";
+
+    pub fn load_str(json: &str) -> Result<Self, DeJsonErr> {
+        DeJson::deserialize_json(json)
+    }
+
+    pub fn load(storage: Option<&dyn eframe::Storage>) -> Self {
+        let json = storage.and_then(|s| s.get_string(Self::STORAGE_KEY));
+        json.and_then(|json| Self::load_str(&json).ok()).unwrap_or_default()
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        storage.set_string(Self::STORAGE_KEY, self.serialize_json());
+    }
+
+    pub fn recording(&self) -> bool {
+        self.enabled && !self.paused
+    }
+
+    pub fn press(&mut self, source: Source, folder: Option<&str>, page: Option<usize>, cell: Option<usize>, label: &str) {
+        self.record(Record { time: now(), source: source.name().to_string(), folder: folder.map(str::to_string), page, cell, text: label.to_string() });
+    }
+
+    pub fn spoken(&mut self, text: &str) {
+        self.record(Record { time: now(), source: "spoken".to_string(), folder: None, page: None, cell: None, text: text.to_string() });
+    }
+
+    fn record(&mut self, record: Record) {
+        if self.recording() {
+            self.records.push(record);
+        }
+    }
+
+    /// Replaces `word` everywhere it appears in the log as a whole word, ignoring case and
+    /// any punctuation around it. Returns how many records were changed.
+    pub fn redact(&mut self, word: &str) -> usize {
+        let word = word.trim_matches(|c: char| !c.is_alphanumeric());
+        if word.is_empty() {
+            return 0;
+        }
+        let mut changed = 0;
+        for record in &mut self.records {
+            let redacted = redact_text(&record.text, word);
+            if redacted != record.text {
+                record.text = redacted;
+                changed += 1;
+            }
+        }
+        info!("Redacted '{}' from {} log entries.", word, changed);
+        changed
+    }
+
+    pub fn clear(&mut self) {
+        self.records.clear();
+    }
+
+    /// The log in the LAM format: one line per event, with a date line, e.g.
+    /// `23:59:59 CTL *[YY-MM-DD=24-02-29]*`, whenever the day changes. Times are UTC.
+    pub fn to_lam(&self) -> String {
+        let mut out = String::from("### CAUTION ###\n");
+        out += "The following data represents personal communication information.\n";
+        out += "Use and release of this information is governed by the user or guardian.\n";
+
+        let mut day = None;
+        for record in &self.records {
+            let (date, time) = date_time(record.time);
+            if day.as_ref() != Some(&date) {
+                out += &format!("{} CTL *[YY-MM-DD={}]*\n", time, &date[2..]);
+                day = Some(date);
+            }
+            if record.source == "spoken" {
+                out += &format!("{} CTL *[Speak]* \"{}\"\n", time, record.text);
+            } else {
+                out += &format!("{} {} \"{}\"\n", time, Source::mnemonic(&record.source), record.text);
+            }
+        }
+        out
+    }
+
+    pub fn to_csv(&self) -> String {
+        let mut out = String::from("time,source,folder,page,cell,text\n");
+        for record in &self.records {
+            let (date, time) = date_time(record.time);
+            let number = |n: Option<usize>| n.map(|n| n.to_string()).unwrap_or_default();
+            out += &format!("{}T{}Z,{},{},{},{},{}\n",
+                date, time, record.source,
+                csv_field(record.folder.as_deref().unwrap_or("")),
+                number(record.page), number(record.cell), csv_field(&record.text));
+        }
+        out
+    }
+}
+
+/// `text` with every whole-word `word` in it replaced, e.g. "Sam" in "Sam's" or "(sam)",
+/// but not in "Samantha".
+fn redact_text(text: &str, word: &str) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let word: Vec<char> = word.chars().collect();
+    let same = |a: &char, b: &char| a.to_lowercase().eq(b.to_lowercase());
+    let mut out = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let end = i + word.len();
+        let starts = i == 0 || !chars[i - 1].is_alphanumeric();
+        let ends = end == chars.len() || chars.get(end).is_some_and(|c| !c.is_alphanumeric());
+        if starts && ends && chars[i..end].iter().zip(&word).all(|(a, b)| same(a, b)) {
+            out += REDACTED;
+            i = end;
+        } else {
+            out.push(chars[i]);
+            i += 1;
+        }
+    }
+    out
+}
+
+/// Reads back a log exported with `to_csv`.
+pub fn from_csv(csv: &str) -> Result<Vec<Record>, String> {
+    let rows = csv_rows(csv);
+    let Some((header, rows)) = rows.split_first() else { return Ok(vec![]) };
+    if header.join(",") != "time,source,folder,page,cell,text" {
+        return Err("not an activity log: the first line should be time,source,folder,page,cell,text".to_string());
+    }
+
+    let mut records = vec![];
+    for (line, row) in rows.iter().enumerate() {
+        let line = line + 2;
+        let [time, source, folder, page, cell, text] = row.as_slice() else {
+            return Err(format!("line {}: expected 6 fields, got {}", line, row.len()));
+        };
+        let number = |field: &str| -> Result<Option<usize>, String> {
+            if field.is_empty() { Ok(None) } else { field.parse().map(Some).map_err(|_| format!("line {}: '{}' isn't a number", line, field)) }
+        };
+        records.push(Record {
+            time: parse_time(time).ok_or_else(|| format!("line {}: '{}' isn't a time like 2024-02-29T23:59:59Z", line, time))?,
+            source: source.clone(),
+            folder: Some(folder.clone()).filter(|f| !f.is_empty()),
+            page: number(page)?,
+            cell: number(cell)?,
+            text: text.clone(),
+        });
+    }
+    Ok(records)
+}
+
+// Quoted fields can contain commas, newlines and doubled quotes.
+fn csv_rows(csv: &str) -> Vec<Vec<String>> {
+    let mut rows = vec![];
+    let mut row = vec![];
+    let mut field = String::new();
+    let mut quoted = false;
+    let mut chars = csv.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (quoted, c) {
+            (true, '"') if chars.peek() == Some(&'"') => { chars.next(); field.push('"'); },
+            (true, '"') => quoted = false,
+            (true, c) => field.push(c),
+            (false, '"') => quoted = true,
+            (false, ',') => row.push(std::mem::take(&mut field)),
+            (false, '\r') => {},
+            (false, '\n') => {
+                row.push(std::mem::take(&mut field));
+                rows.push(std::mem::take(&mut row));
+            },
+            (false, c) => field.push(c),
+        }
+    }
+    if !field.is_empty() || !row.is_empty() {
+        row.push(field);
+        rows.push(row);
+    }
+    rows
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// ("YYYY-MM-DD", "HH:MM:SS") in UTC.
+pub fn date_time(millis: u64) -> (String, String) {
+    let secs = millis / 1000;
+    let (days, secs) = (secs / 86400, secs % 86400);
+
+    // Howard Hinnant's days-to-civil algorithm.
+    let z = days as i64 + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    (format!("{:04}-{:02}-{:02}", year, month, day), format!("{:02}:{:02}:{:02}", secs / 3600, secs / 60 % 60, secs % 60))
+}
+
+/// Milliseconds since 1970 for a UTC time like 2024-02-29T23:59:59Z.
+fn parse_time(time: &str) -> Option<u64> {
+    let (date, time) = time.strip_suffix('Z')?.split_once('T')?;
+    let date: Vec<i64> = date.split('-').map(|n| n.parse().ok()).collect::<Option<_>>()?;
+    let time: Vec<u64> = time.split(':').map(|n| n.parse().ok()).collect::<Option<_>>()?;
+    let ([year, month, day], [hours, minutes, seconds]) = (date.as_slice(), time.as_slice()) else { return None };
+
+    // The inverse of the algorithm in `date_time`.
+    let year = if *month <= 2 { year - 1 } else { *year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let doy = (153 * (if *month > 2 { month - 3 } else { month + 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = u64::try_from(era * 146097 + doe - 719468).ok()?;
+    Some(((days * 86400) + hours * 3600 + minutes * 60 + seconds) * 1000)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn now() -> u64 {
+    std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_millis() as u64).unwrap_or(0)
+}
+
+// std's clock panics in the browser.
+#[cfg(target_arch = "wasm32")]
+fn now() -> u64 {
+    js_sys::Date::now() as u64
+}
+
+/// Puts an exported log somewhere the user can get at it: a file in the current
+/// directory, or a download on the web. Returns where it went.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn export(file_name: &str, contents: &str) -> Result<String, String> {
+    std::fs::write(file_name, contents).map_err(|err| err.to_string())?;
+    let path = std::fs::canonicalize(file_name).map_err(|err| err.to_string())?;
+    Ok(path.display().to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+pub fn export(file_name: &str, contents: &str) -> Result<String, String> {
+    use wasm_bindgen::JsCast;
+
+    let error = |err: wasm_bindgen::JsValue| format!("{:?}", err);
+    let document = web_sys::window().and_then(|w| w.document()).ok_or("no document to download from")?;
+    let parts = js_sys::Array::of1(&contents.into());
+    let blob = web_sys::Blob::new_with_str_sequence_and_options(&parts, web_sys::BlobPropertyBag::new().type_("text/plain")).map_err(error)?;
+    let url = web_sys::Url::create_object_url_with_blob(&blob).map_err(error)?;
+
+    let link: web_sys::HtmlAnchorElement = document.create_element("a").map_err(error)?.unchecked_into();
+    link.set_href(&url);
+    link.set_download(file_name);
+    link.click();
+    web_sys::Url::revoke_object_url(&url).map_err(error)?;
+    Ok(format!("your downloads, as {}", file_name))
+}
+
+#[test]
+fn test_lam() {
+    let mut lam = Lam::load_str("{}").unwrap();
+    lam.spoken("not logged");
+    assert!(lam.records.is_empty());
+
+    lam.enabled = true;
+    lam.press(Source::Board, Some("Home"), Some(0), Some(3), "want");
+    lam.paused = true;
+    lam.press(Source::Board, Some("Home"), Some(0), Some(4), "paused");
+    lam.paused = false;
+    lam.press(Source::Keyboard, None, None, None, "Sam, hi");
+    lam.spoken("want Sam, hi");
+    assert_eq!(3, lam.records.len());
+    assert_eq!(lam, Lam::load_str(&lam.serialize_json()).unwrap());
+
+    // 2024-02-29 23:59:59 UTC, then the next day.
+    for (record, time) in lam.records.iter_mut().zip([1709251199000, 1709251200000, 1709251205000]) {
+        record.time = time;
+    }
+    assert_eq!(2, lam.redact("sam,"));
+    assert_eq!(
+        "23:59:59 CTL *[YY-MM-DD=24-02-29]*\n23:59:59 SMP \"want\"\n00:00:00 CTL *[YY-MM-DD=24-03-01]*\n00:00:00 SPE \"[redacted], hi\"\n00:00:05 CTL *[Speak]* \"want [redacted], hi\"\n",
+        lam.to_lam().split_once("guardian.\n").unwrap().1
+    );
+    assert_eq!(
+        vec!["time,source,folder,page,cell,text", "2024-02-29T23:59:59Z,board,Home,0,3,want"],
+        lam.to_csv().lines().take(2).collect::<Vec<_>>()
+    );
+
+    // Punctuation and line breaks around a name don't hide it, but longer words aren't cut.
+    let mut names = Lam { enabled: true, ..Default::default() };
+    for text in ["Sam.", "Sam's turn", "ask sam\n", "(SAM)", "Samantha and Sam Jones", "José, hi"] {
+        names.spoken(text);
+    }
+    assert_eq!(1, names.redact("sam jones"));
+    assert_eq!(4, names.redact("Sam"));
+    assert_eq!(1, names.redact("josé"));
+    assert_eq!(
+        vec!["[redacted].", "[redacted]'s turn", "ask [redacted]\n", "([redacted])", "Samantha and [redacted]", "[redacted], hi"],
+        names.records.iter().map(|r| r.text.as_str()).collect::<Vec<_>>()
+    );
+
+    lam.records[0].text = "a \"b\",\nc".to_string();
+    assert!(lam.to_csv().contains(",0,3,\"a \"\"b\"\",\nc\"\n"));
+    assert_eq!(lam.records, from_csv(&lam.to_csv()).unwrap());
+    assert!(from_csv("time,source\n").is_err());
+}
@@ -0,0 +1,218 @@
+use log::warn;
+use nanoserde::{DeJson, SerJson};
+
+/// A part of the screen.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Region {
+    Speak,
+    Panel,
+    Delete,
+    Clear,
+    Selector,
+    Board,
+    Related,
+    Variants,
+}
+
+impl Region {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim() {
+            "speak" => Some(Self::Speak),
+            "panel" => Some(Self::Panel),
+            "delete" => Some(Self::Delete),
+            "clear" => Some(Self::Clear),
+            "selector" => Some(Self::Selector),
+            "board" => Some(Self::Board),
+            "related" => Some(Self::Related),
+            "variants" => Some(Self::Variants),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HotbarPosition {
+    Top,
+    Bottom,
+    Hidden,
+}
+
+/// The drawer's handle, relative to a board button's height.
+pub const DRAWER_HANDLE_HEIGHT: f32 = 0.5;
+
+fn default_top() -> Vec<String> {
+    ["speak", "panel", "delete", "clear"].iter().map(|s| s.to_string()).collect()
+}
+
+fn default_columns() -> Vec<String> {
+    ["selector", "board", "related", "variants"].iter().map(|s| s.to_string()).collect()
+}
+
+/// Where everything goes on screen.
+///
+/// The screen has two rows: `top` (Speak, the panel, Delete, Clear) and
+/// `columns` (the folder selector, the board, the related and variant words).
+/// Each is listed left to right; leave a region out to hide it.
+#[derive(Clone, Debug, DeJson, SerJson, PartialEq)]
+pub struct Layout {
+    #[nserde(default = "10.0")]
+    pub margin: f32,
+    #[nserde(default = "5.0")]
+    pub spacing: f32,
+    #[nserde(default_with = "default_top")]
+    pub top: Vec<String>,
+    #[nserde(default_with = "default_columns")]
+    pub columns: Vec<String>,
+    /// Flips both rows, e.g. for someone who points with their left hand.
+    /// The board itself stays the same, so words don't move.
+    #[nserde(default)]
+    pub mirrored: bool,
+    /// "top", "bottom", or "hidden".
+    #[nserde(default = "bottom")]
+    pub hotbar: String,
+    // Sizes, relative to a board button.
+    #[nserde(default = "1.0")]
+    pub top_height: f32,
+    #[nserde(default = "1.0")]
+    pub selector_width: f32,
+    #[nserde(default = "1.0")]
+    pub related_width: f32,
+    #[nserde(default = "1.0")]
+    pub variants_width: f32,
+    /// On screens taller than they are wide, stack everything instead: the folder
+    /// selector across the top, then the panel, the board, and a drawer for
+    /// related words and variants.
+    #[nserde(default = "true")]
+    pub responsive: bool,
+    /// Buttons never get smaller than this, in points. The screen scrolls instead.
+    #[nserde(default = "44.0")]
+    pub min_target: f32,
+}
+
+impl Default for Layout {
+    fn default() -> Self {
+        Self {
+            margin: 10.0,
+            spacing: 5.0,
+            top: default_top(),
+            columns: default_columns(),
+            mirrored: false,
+            hotbar: "bottom".to_string(),
+            top_height: 1.0,
+            selector_width: 1.0,
+            related_width: 1.0,
+            variants_width: 1.0,
+            responsive: true,
+            min_target: 44.0,
+        }
+    }
+}
+
+impl Layout {
+    fn regions(&self, names: &[String]) -> Vec<Region> {
+        let mut regions: Vec<Region> = names.iter()
+            .filter_map(|name| {
+                let region = Region::parse(name);
+                if region.is_none() {
+                    warn!("!!! Layout has an unknown region: '{}'", name);
+                }
+                region
+            })
+            .collect();
+
+        if self.mirrored {
+            regions.reverse();
+        }
+
+        regions
+    }
+
+    pub fn top_row(&self) -> Vec<Region> {
+        self.regions(&self.top)
+    }
+
+    pub fn bottom_row(&self) -> Vec<Region> {
+        self.regions(&self.columns)
+    }
+
+    pub fn shows(&self, region: Region) -> bool {
+        self.top_row().contains(&region) || self.bottom_row().contains(&region)
+    }
+
+    pub fn hotbar_position(&self) -> HotbarPosition {
+        match self.hotbar.as_str() {
+            "top" => HotbarPosition::Top,
+            "hidden" => HotbarPosition::Hidden,
+            _ => HotbarPosition::Bottom,
+        }
+    }
+
+    /// How many buttons wide a region in the bottom row is.
+    pub fn width_of(&self, region: Region, cols: usize) -> f32 {
+        match region {
+            Region::Selector => self.selector_width,
+            Region::Board => cols as f32,
+            Region::Related => self.related_width,
+            Region::Variants => self.variants_width,
+            _ => 1.0,
+        }
+    }
+
+    /// The whole screen's width, in buttons.
+    pub fn width_units(&self, cols: usize) -> f32 {
+        self.bottom_row().iter().map(|r| self.width_of(*r, cols)).sum()
+    }
+
+    /// The whole screen's height, in buttons.
+    pub fn height_units(&self, rows: usize) -> f32 {
+        let hotbar = if self.hotbar_position() == HotbarPosition::Hidden { 0.0 } else { 1.0 };
+        let top = if self.top_row().is_empty() { 0.0 } else { self.top_height };
+        top + (rows as f32) + hotbar
+    }
+
+    pub fn is_portrait(&self, width: f32, height: f32) -> bool {
+        self.responsive && height > width
+    }
+
+    /// Top row regions that still make sense in portrait; the rest have their own places.
+    pub fn portrait_top_row(&self) -> Vec<Region> {
+        self.top_row().into_iter().filter(|r| matches!(r, Region::Speak | Region::Panel | Region::Delete | Region::Clear)).collect()
+    }
+
+    pub fn has_drawer(&self) -> bool {
+        self.shows(Region::Related) || self.shows(Region::Variants)
+    }
+
+    /// The whole screen's height in portrait, in buttons.
+    pub fn portrait_height_units(&self, rows: usize) -> f32 {
+        let selector = if self.shows(Region::Selector) { 1.0 } else { 0.0 };
+        let hotbar = if self.hotbar_position() == HotbarPosition::Hidden { 0.0 } else { 1.0 };
+        let top = if self.portrait_top_row().is_empty() { 0.0 } else { self.top_height };
+        let drawer = if self.has_drawer() { DRAWER_HANDLE_HEIGHT } else { 0.0 };
+        selector + top + (rows as f32) + hotbar + drawer
+    }
+}
+
+#[test]
+fn test_layout() {
+    // The defaults match the original, fixed, layout.
+    let layout = Layout::deserialize_json("{}").unwrap();
+    assert_eq!(Layout::default(), layout);
+    assert_eq!(9.0 + 3.0, layout.width_units(9));
+    assert_eq!(6.0 + 2.0, layout.height_units(6));
+
+    let json = r#"{"columns": ["selector", "board", "fish"], "mirrored": true, "hotbar": "hidden", "selector_width": 1.5}"#;
+    let layout = Layout::deserialize_json(json).unwrap();
+    assert_eq!(vec![Region::Board, Region::Selector], layout.bottom_row());
+    assert_eq!(vec![Region::Clear, Region::Delete, Region::Panel, Region::Speak], layout.top_row());
+    assert!(!layout.shows(Region::Related));
+    assert_eq!(HotbarPosition::Hidden, layout.hotbar_position());
+    assert_eq!(4.0 + 1.5, layout.width_units(4));
+    assert_eq!(6.0 + 1.0, layout.height_units(6));
+
+    assert!(!layout.is_portrait(1280.0, 720.0));
+    assert!(layout.is_portrait(600.0, 1024.0));
+    assert!(!layout.has_drawer());
+    // Selector strip, top row, 6 rows, no hotbar, no drawer.
+    assert_eq!(1.0 + 1.0 + 6.0, layout.portrait_height_units(6));
+}
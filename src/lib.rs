@@ -1,9 +1,33 @@
+pub mod action;
 pub mod app;
 pub mod cli;
+pub mod color;
+pub mod diff;
+pub mod editor;
+pub mod events;
+pub mod format;
+pub mod i18n;
+pub mod lam;
 pub mod system;
 pub mod button;
+pub mod layout;
+pub mod loader;
+pub mod lock;
+pub mod motor;
+pub mod navigation;
 pub mod panel;
+pub mod pdf;
+pub mod positions;
+pub mod profile;
+pub mod render;
+pub mod report;
+pub mod search;
+pub mod settings;
 pub mod speech;
+pub mod stats;
+pub mod theme;
+pub mod users;
+pub mod web_speech;
 
 #[cfg(target_arch = "wasm32")]
 mod wasm;
@@ -0,0 +1,119 @@
+//! Loading a System while the app is running. The web build has no `--system`, so it
+//! loads one from a `?system=URL` query parameter, a file the user picks, or one that
+//! was imported earlier and kept in storage.
+
+use eframe::egui;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// Where an imported System is kept between runs, as JSON. Empty means none.
+pub const STORAGE_KEY: &str = "system";
+
+pub struct Loaded {
+    /// Where it came from, e.g. a URL or file name, for messages.
+    pub source: String,
+    pub contents: Result<String, String>,
+}
+
+/// Collects Systems as they finish loading in the background, for the app to pick up
+/// on its next frame.
+#[derive(Clone)]
+pub struct Loader {
+    ctx: egui::Context,
+    loaded: Rc<RefCell<Vec<Loaded>>>,
+}
+
+impl Loader {
+    pub fn new(ctx: &egui::Context) -> Self {
+        Self { ctx: ctx.clone(), loaded: Rc::default() }
+    }
+
+    pub fn push(&self, source: &str, contents: Result<String, String>) {
+        self.loaded.borrow_mut().push(Loaded { source: source.to_string(), contents });
+        self.ctx.request_repaint();
+    }
+
+    pub fn take(&self) -> Vec<Loaded> {
+        std::mem::take(&mut *self.loaded.borrow_mut())
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn fetch(&self, url: &str) {
+        let loader = self.clone();
+        let url = url.to_string();
+        wasm_bindgen_futures::spawn_local(async move {
+            let contents = fetch_text(&url).await;
+            loader.push(&url, contents);
+        });
+    }
+
+    /// Asks the user for a System file. Nothing happens if they cancel.
+    #[cfg(target_arch = "wasm32")]
+    pub fn pick_file(&self) {
+        let Some(document) = web_sys::window().and_then(|w| w.document()) else { return };
+        let Ok(input) = document.create_element("input") else { return };
+        let input: web_sys::HtmlInputElement = input.unchecked_into();
+        input.set_type("file");
+        input.set_accept(".json,.sgs,.txt");
+
+        let loader = self.clone();
+        let picked = input.clone();
+        let on_change = Closure::once_into_js(move || {
+            let Some(file) = picked.files().and_then(|files| files.get(0)) else { return };
+            wasm_bindgen_futures::spawn_local(async move {
+                let contents = JsFuture::from(file.text()).await.map_err(js_error).and_then(as_text);
+                loader.push(&file.name(), contents);
+            });
+        });
+        input.set_onchange(Some(on_change.unchecked_ref()));
+        input.click();
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::{closure::Closure, JsCast, JsValue};
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen_futures::JsFuture;
+
+#[cfg(target_arch = "wasm32")]
+fn js_error(err: JsValue) -> String {
+    err.as_string().unwrap_or_else(|| format!("{:?}", err))
+}
+
+#[cfg(target_arch = "wasm32")]
+fn as_text(value: JsValue) -> Result<String, String> {
+    value.as_string().ok_or_else(|| "not a text file".to_string())
+}
+
+#[cfg(target_arch = "wasm32")]
+async fn fetch_text(url: &str) -> Result<String, String> {
+    let window = web_sys::window().ok_or("no window to fetch from")?;
+    let response: web_sys::Response = JsFuture::from(window.fetch_with_str(url)).await.map_err(js_error)?.unchecked_into();
+    if !response.ok() {
+        return Err(format!("HTTP status {}", response.status()));
+    }
+    let text = JsFuture::from(response.text().map_err(js_error)?).await.map_err(js_error)?;
+    as_text(text)
+}
+
+/// The URL in the page's `?system=` query parameter, if any.
+#[cfg(target_arch = "wasm32")]
+pub fn query_url() -> Option<String> {
+    let search = web_sys::window()?.location().search().ok()?;
+    web_sys::UrlSearchParams::new_with_str(&search).ok()?.get("system")
+}
+
+#[test]
+fn test_loader() {
+    let loader = Loader::new(&egui::Context::default());
+    // Clones share what's been loaded, like the background tasks do.
+    loader.clone().push("a.json", Ok("{}".to_string()));
+    loader.push("b.json", Err("HTTP status 404".to_string()));
+
+    let loaded = loader.take();
+    assert_eq!(vec!["a.json", "b.json"], loaded.iter().map(|l| l.source.as_str()).collect::<Vec<_>>());
+    assert!(loaded[1].contents.is_err());
+    assert!(loader.take().is_empty());
+}
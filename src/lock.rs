@@ -0,0 +1,58 @@
+use nanoserde::{DeJson, DeJsonErr, SerJson};
+
+/// "Communication only" mode: no settings, editing, or quitting until a caregiver unlocks it.
+#[derive(Clone, Debug, Default, DeJson, SerJson, PartialEq)]
+pub struct Lock {
+    #[nserde(default)]
+    pub locked: bool,
+    /// Folder ids that can't be opened while locked. Their sub-folders are blocked too.
+    #[nserde(default)]
+    pub blocked_folders: Vec<String>,
+}
+
+impl Lock {
+    pub const STORAGE_KEY: &'static str = "lock";
+
+    pub fn load_str(json: &str) -> Result<Self, DeJsonErr> {
+        DeJson::deserialize_json(json)
+    }
+
+    pub fn load(storage: Option<&dyn eframe::Storage>) -> Self {
+        let json = storage.and_then(|s| s.get_string(Self::STORAGE_KEY));
+        json.and_then(|json| Self::load_str(&json).ok()).unwrap_or_default()
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        storage.set_string(Self::STORAGE_KEY, self.serialize_json());
+    }
+
+    pub fn blocks(&self, folder_id: &str) -> bool {
+        self.locked && self.blocked_folders.iter().any(|blocked| {
+            folder_id == blocked || folder_id.strip_prefix(blocked.as_str()).is_some_and(|rest| rest.starts_with("::"))
+        })
+    }
+
+    pub fn set_blocked(&mut self, folder_id: &str, blocked: bool) {
+        self.blocked_folders.retain(|f| f != folder_id);
+        if blocked {
+            self.blocked_folders.push(folder_id.to_string());
+        }
+    }
+}
+
+#[test]
+fn test_lock() {
+    let mut lock = Lock::load_str("{}").unwrap();
+    lock.set_blocked("Verbs", true);
+    assert!(!lock.blocks("Verbs"));
+
+    lock.locked = true;
+    assert!(lock.blocks("Verbs"));
+    assert!(lock.blocks("Verbs::Motion"));
+    assert!(!lock.blocks("Verbsy"));
+    assert!(!lock.blocks("Nouns"));
+
+    lock.set_blocked("Verbs", false);
+    assert!(!lock.blocks("Verbs"));
+    assert_eq!(lock, Lock::load_str(&lock.serialize_json()).unwrap());
+}
@@ -3,6 +3,8 @@ use eframe::egui;
 #[cfg(not(target_arch = "wasm32"))]
 use env_logger::Env;
 #[cfg(not(target_arch = "wasm32"))]
+use sgs::cli;
+#[cfg(not(target_arch = "wasm32"))]
 use sgs::app::{
     MIN_WIDTH,
     MIN_HEIGHT,
@@ -20,6 +22,9 @@ fn main() { }
 fn main() {
     env_logger::Builder::from_env(Env::default().default_filter_or("info")).init();
 
+    // Handles --help, and options that don't start the GUI.
+    cli::process_args(cli::parse_args().expect("Invalid arguments"));
+
     let native_options = eframe::NativeOptions {
         min_window_size: Some(egui::vec2(MIN_WIDTH, MIN_HEIGHT)),
         ..Default::default()
@@ -0,0 +1,237 @@
+//! `sgs motor`: how many taps each word in a System takes to reach, and which
+//! frequently used words are worth moving somewhere quicker.
+
+use crate::navigation;
+use crate::system::System;
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::fmt;
+
+/// How many examples to list for each problem.
+const EXAMPLES: usize = 20;
+/// Words that take at least this many taps are flagged as buried, e.g. page 3 of a
+/// subfolder: select, open, "->", "->", press.
+pub const BURIED_TAPS: usize = 5;
+/// How many of the most frequent words are checked for being buried.
+const FREQUENT: usize = 1000;
+
+/// The fewest taps to reach a word, and where that is.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Reach {
+    pub word: String,
+    pub taps: usize,
+    /// Folder id, or "hotbar".
+    pub folder: String,
+    /// Starting from 1.
+    pub page: usize,
+}
+
+/// How often words are used, from a list with one word per line, most frequent first,
+/// like the one the bundled System was made from. A count may follow each word;
+/// without one, Zipf's law is assumed (the word ranked n is used 1/n as often as the first).
+#[derive(Clone, Debug, Default)]
+pub struct Frequencies {
+    /// (lowercased word, weight), most frequent first.
+    pub words: Vec<(String, f64)>,
+}
+
+impl Frequencies {
+    pub fn parse(list: &str) -> Self {
+        let mut words: Vec<(String, f64)> = vec![];
+        for line in list.lines() {
+            let mut fields = line.split(|c: char| c.is_whitespace() || c == ',').filter(|f| !f.is_empty());
+            let Some(word) = fields.next() else { continue };
+            let rank = words.len() + 1;
+            let weight = fields.next().and_then(|count| count.parse().ok()).unwrap_or(1.0 / rank as f64);
+            words.push((word.to_lowercase(), weight));
+        }
+        words.sort_by(|a, b| b.1.total_cmp(&a.1));
+        Self { words }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Analysis {
+    pub name: String,
+    /// Each word, reached the quickest way there is. Sorted by word.
+    pub words: Vec<Reach>,
+    /// Words that can't be reached at all, e.g. in a folder nothing links to.
+    pub unreachable: Vec<String>,
+    /// Frequent words that take `BURIED_TAPS` or more, most frequent first.
+    pub buried: Vec<Reach>,
+    /// Words that would save the most taps in the hotbar, best first, with the
+    /// (frequency-weighted) taps they'd save.
+    pub hotbar_candidates: Vec<(Reach, f64)>,
+    /// Mean taps per word, weighted by how often each is used, if frequencies were given.
+    pub weighted_mean: Option<f64>,
+}
+
+impl Analysis {
+    pub fn new(system: &System, frequencies: Option<&Frequencies>) -> Self {
+        let pages = navigation::taps_to_pages(system);
+        let mut best: HashMap<String, Reach> = HashMap::new();
+        let mut keep = |reach: Reach| {
+            let current = best.entry(reach.word.clone()).or_insert_with(|| reach.clone());
+            if reach.taps < current.taps {
+                *current = reach;
+            }
+        };
+
+        let mut unreachable = vec![];
+        for (folder_idx, folder) in system.folders.iter().enumerate() {
+            for (index, button) in folder.buttons.iter().enumerate() {
+                let Some(button) = button.as_ref().filter(|b| !b.hidden && b.folder.is_none() && b.action.is_none()) else { continue };
+                let (page, _, _) = folder.position_of(index);
+                match pages.get(&(folder_idx, page)) {
+                    Some(taps) => keep(Reach { word: button.label.to_lowercase(), taps: taps + 1, folder: folder.id.clone(), page: page + 1 }),
+                    None => unreachable.push(button.label.to_lowercase()),
+                }
+            }
+        }
+
+        // The hotbar is always there. It pages by the width of the open folder; assume the widest.
+        let (_, cols) = system.grid_size();
+        for (index, button) in system.hotbar.buttons.iter().enumerate() {
+            let Some(button) = button.as_ref().filter(|b| !b.hidden && b.action.is_none()) else { continue };
+            let page = index / cols.max(1);
+            keep(Reach { word: button.label.to_lowercase(), taps: page + 1, folder: "hotbar".to_string(), page: page + 1 });
+        }
+
+        let mut words: Vec<Reach> = best.into_values().collect();
+        words.sort_by(|a, b| a.word.cmp(&b.word));
+        unreachable.retain(|word| words.binary_search_by(|r| r.word.cmp(word)).is_err());
+        unreachable.sort();
+        unreachable.dedup();
+
+        let mut analysis = Self { name: system.name.clone(), words, unreachable, ..Default::default() };
+        if let Some(frequencies) = frequencies {
+            analysis.use_frequencies(frequencies);
+        }
+        analysis
+    }
+
+    pub fn reach(&self, word: &str) -> Option<&Reach> {
+        self.words.binary_search_by(|r| r.word.as_str().cmp(word)).ok().map(|i| &self.words[i])
+    }
+
+    fn use_frequencies(&mut self, frequencies: &Frequencies) {
+        let mut total = 0.0;
+        let mut taps = 0.0;
+        let mut buried = vec![];
+        let mut candidates = vec![];
+        for (rank, (word, weight)) in frequencies.words.iter().enumerate() {
+            let Some(reach) = self.reach(word).cloned() else { continue };
+            total += weight;
+            taps += weight * reach.taps as f64;
+
+            if rank < FREQUENT && reach.taps >= BURIED_TAPS {
+                buried.push(reach.clone());
+            }
+            // Anything in the hotbar takes at least one tap.
+            if reach.folder != "hotbar" && reach.taps > 1 {
+                let saved = weight * (reach.taps - 1) as f64;
+                candidates.push((reach, saved));
+            }
+        }
+        candidates.sort_by(|a, b| b.1.total_cmp(&a.1));
+        candidates.truncate(EXAMPLES);
+        self.buried = buried;
+        self.hotbar_candidates = candidates;
+        self.weighted_mean = (total > 0.0).then(|| taps / total);
+    }
+
+    /// How many words take each number of taps.
+    pub fn histogram(&self) -> BTreeMap<usize, usize> {
+        let mut histogram = BTreeMap::new();
+        for reach in &self.words {
+            *histogram.entry(reach.taps).or_default() += 1;
+        }
+        histogram
+    }
+
+    pub fn mean(&self) -> f64 {
+        let taps: usize = self.words.iter().map(|r| r.taps).sum();
+        taps as f64 / self.words.len().max(1) as f64
+    }
+}
+
+impl fmt::Display for Reach {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}: {} taps ({}, page {})", self.word, self.taps, self.folder, self.page)
+    }
+}
+
+fn more(f: &mut fmt::Formatter, total: usize) -> fmt::Result {
+    if total > EXAMPLES {
+        writeln!(f, "    ...and {} more", total - EXAMPLES)?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for Analysis {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "System: {}", self.name)?;
+        writeln!(f, "Words: {}, taking {:.2} taps on average", self.words.len(), self.mean())?;
+        if let Some(mean) = self.weighted_mean {
+            writeln!(f, "Taps per word, weighted by frequency: {:.2}", mean)?;
+        }
+        writeln!(f)?;
+
+        writeln!(f, "Words by taps to reach:")?;
+        for (taps, words) in self.histogram() {
+            writeln!(f, "    {:>3} taps: {}", taps, words)?;
+        }
+        writeln!(f)?;
+
+        if !self.unreachable.is_empty() {
+            writeln!(f, "Unreachable words: {}", self.unreachable.len())?;
+            for word in self.unreachable.iter().take(EXAMPLES) {
+                writeln!(f, "    {:?}", word)?;
+            }
+            more(f, self.unreachable.len())?;
+            writeln!(f)?;
+        }
+
+        if self.weighted_mean.is_none() {
+            return writeln!(f, "(Give a frequency list with --frequencies to find buried words and hotbar candidates.)");
+        }
+
+        writeln!(f, "Frequent words taking {} or more taps: {}", BURIED_TAPS, self.buried.len())?;
+        for reach in self.buried.iter().take(EXAMPLES) {
+            writeln!(f, "    {}", reach)?;
+        }
+        more(f, self.buried.len())?;
+        writeln!(f)?;
+
+        writeln!(f, "Hotbar candidates (most taps saved first):")?;
+        for (reach, _) in &self.hotbar_candidates {
+            writeln!(f, "    {}", reach)?;
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_motor() {
+    // Animals has two buttons per page.
+    let mut system = crate::format::from_text("#sgs\nname = \"Motor\"\nrows = 1\ncols = 2\n\
+        :Home\n\t\"the\"\t\"Animals\" -> Home::Animals\n.\n\
+        :Home::Animals\n\t\"cat\"\t\"dog\"\n\t\"bird\"\t\"cow\"\n\t\"fish\"\t\"the\"\n.\n\
+        :Lost\n\t\"moose\"\n.\n").unwrap();
+    system.folders[2].toplevel = false;
+
+    let frequencies = Frequencies::parse("the\nfish\ncat\nzebra\n");
+    assert_eq!(("the".to_string(), 1.0), frequencies.words[0]);
+    assert_eq!(Frequencies::parse("a 5\nb, 10\n").words[0], ("b".to_string(), 10.0));
+
+    let analysis = Analysis::new(&system, Some(&frequencies));
+    assert_eq!(Some(&Reach { word: "the".to_string(), taps: 2, folder: "Home".to_string(), page: 1 }), analysis.reach("the"));
+    // Select Home, open Animals, "->" twice, press.
+    assert_eq!(5, analysis.reach("fish").unwrap().taps);
+    assert_eq!(vec!["moose"], analysis.unreachable);
+    assert_eq!(vec!["fish"], analysis.buried.iter().map(|r| r.word.as_str()).collect::<Vec<_>>());
+    // "fish" saves 4 taps at half the frequency of "the", which saves one.
+    assert_eq!(vec!["fish", "the", "cat"], analysis.hotbar_candidates.iter().map(|(r, _)| r.word.as_str()).collect::<Vec<_>>());
+    assert!(analysis.to_string().contains("\"fish\": 5 taps (Home::Animals, page 3)"));
+}
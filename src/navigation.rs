@@ -0,0 +1,279 @@
+use crate::button::Button;
+use crate::system::System;
+
+use std::cmp;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, VecDeque};
+
+// (folder, page)
+type State = (usize, usize);
+
+/// Where the user is, and how they got there.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Navigation {
+    pub folder: usize,
+    pub page: usize,
+    home: usize,
+    history: Vec<State>,
+}
+
+impl Navigation {
+    pub fn new(home: usize) -> Self {
+        Self { folder: home, page: 0, home, history: vec![] }
+    }
+
+    /// Opens `folder` on its first page, remembering where we were.
+    pub fn open(&mut self, folder: usize) {
+        if folder == self.folder {
+            self.page = 0;
+            return;
+        }
+
+        self.history.push((self.folder, self.page));
+        self.folder = folder;
+        self.page = 0;
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        !self.history.is_empty()
+    }
+
+    pub fn back(&mut self) {
+        if let Some((folder, page)) = self.history.pop() {
+            self.folder = folder;
+            self.page = page;
+        }
+    }
+
+    pub fn home(&mut self) {
+        self.history.clear();
+        self.folder = self.home;
+        self.page = 0;
+    }
+}
+
+/// A single tap on the way to a button.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Step {
+    /// Tap a top-level folder in the folder selector.
+    SelectFolder { folder: usize },
+    /// Tap the "->" button while on `page` of `folder`.
+    NextPage { folder: usize, page: usize },
+    /// Tap a button that links to another folder.
+    OpenFolder { folder: usize, index: usize },
+    /// Tap the button itself.
+    Press { folder: usize, index: usize },
+    /// Tap an entry in the related words column.
+    Related { index: usize },
+    /// Tap an entry in the variants column.
+    Variant { index: usize },
+}
+
+/// Finds the shortest sequence of taps that reaches the button at `index` in `folder`,
+/// starting from the folder selector.
+pub fn path_to(system: &System, folder: usize, index: usize) -> Option<Vec<Step>> {
+    path_to_visible(system, folder, index, &|_, button| !button.hidden)
+}
+
+/// Like [`path_to`], but only through buttons that `visible(folder, button)` allows,
+/// e.g. the ones a user's profile hasn't hidden.
+pub fn path_to_visible(system: &System, folder: usize, index: usize, visible: &dyn Fn(usize, &Button) -> bool) -> Option<Vec<Step>> {
+    let target_folder = system.folders.get(folder)?;
+    if !visible(folder, target_folder.buttons.get(index)?.as_ref()?) {
+        return None;
+    }
+    let (target_page, _, _) = target_folder.position_of(index);
+    let target = (folder, target_page);
+
+    // State => (previous state, the step that got us here)
+    let mut came_from: HashMap<State, (Option<State>, Step)> = HashMap::new();
+    let mut queue = VecDeque::new();
+
+    for toplevel in system.toplevel_folders() {
+        if let Some(idx) = system.folder_index(&toplevel.id) {
+            came_from.entry((idx, 0)).or_insert((None, Step::SelectFolder { folder: idx }));
+            queue.push_back((idx, 0));
+        }
+    }
+
+    while let Some(state) = queue.pop_front() {
+        if state == target {
+            let mut steps = vec![Step::Press { folder, index }];
+            let mut current = Some(state);
+            while let Some((previous, step)) = current.and_then(|s| came_from.get(&s)) {
+                steps.push(*step);
+                current = *previous;
+            }
+            steps.reverse();
+            return Some(steps);
+        }
+
+        for (next, step) in edges(system, state, visible) {
+            if let Entry::Vacant(entry) = came_from.entry(next) {
+                entry.insert((Some(state), step));
+                queue.push_back(next);
+            }
+        }
+    }
+
+    None
+}
+
+/// Where a single tap can go from `page` of `folder`: folders it links to, and its next page.
+fn edges(system: &System, (folder, page): State, visible: &dyn Fn(usize, &Button) -> bool) -> Vec<(State, Step)> {
+    let current = &system.folders[folder];
+    let mut edges = vec![];
+
+    let start = page * current.buttons_per_page();
+    let end = cmp::min(start + current.buttons_per_page(), current.buttons.len());
+    for idx in start..end {
+        let link = current.buttons[idx].as_ref().filter(|b| visible(folder, b)).and_then(|b| b.folder.as_ref());
+        if let Some(linked) = link.and_then(|id| system.folder_index(id)) {
+            edges.push(((linked, 0), Step::OpenFolder { folder, index: idx }));
+        }
+    }
+
+    if current.needs_pagination() {
+        edges.push(((folder, current.next_page(page)), Step::NextPage { folder, page }));
+    }
+    edges
+}
+
+/// The fewest taps it takes to get to each reachable (folder, page), starting from the
+/// folder selector like [`path_to`]. Pressing a button there is one more tap.
+pub fn taps_to_pages(system: &System) -> HashMap<(usize, usize), usize> {
+    let visible = |_: usize, button: &Button| !button.hidden;
+    let mut taps = HashMap::new();
+    let mut queue = VecDeque::new();
+
+    for toplevel in system.toplevel_folders() {
+        if let Some(idx) = system.folder_index(&toplevel.id) {
+            taps.entry((idx, 0)).or_insert(1);
+            queue.push_back((idx, 0));
+        }
+    }
+
+    while let Some(state) = queue.pop_front() {
+        let next_taps = taps[&state] + 1;
+        for (next, _) in edges(system, state, &visible) {
+            if let Entry::Vacant(entry) = taps.entry(next) {
+                entry.insert(next_taps);
+                queue.push_back(next);
+            }
+        }
+    }
+    taps
+}
+
+/// Walks someone through the taps needed to reach a word (aided language stimulation).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Modeling {
+    pub word: String,
+    pub steps: Vec<Step>,
+    pub current: usize,
+}
+
+impl Modeling {
+    pub fn new(word: String, steps: Vec<Step>) -> Self {
+        Self { word, steps, current: 0 }
+    }
+
+    pub fn current_step(&self) -> Option<&Step> {
+        self.steps.get(self.current)
+    }
+
+    pub fn is_current(&self, step: Step) -> bool {
+        self.current_step() == Some(&step)
+    }
+}
+
+/// Records a tap. Moves on to the next step if it was the expected one; otherwise,
+/// or once the last step is done, modeling stops.
+pub fn advance(modeling: &mut Option<Modeling>, step: Step) {
+    if let Some(m) = modeling {
+        if m.is_current(step) && m.current + 1 < m.steps.len() {
+            m.current += 1;
+            return;
+        }
+    }
+
+    *modeling = None;
+}
+
+#[test]
+fn test_navigation() {
+    let mut nav = Navigation::new(0);
+    assert!(!nav.can_go_back());
+
+    nav.page = 2;
+    nav.open(3);
+    nav.page = 1;
+    nav.open(5);
+    assert_eq!((5, 0), (nav.folder, nav.page));
+
+    nav.back();
+    assert_eq!((3, 1), (nav.folder, nav.page));
+    nav.back();
+    assert_eq!((0, 2), (nav.folder, nav.page));
+    assert!(!nav.can_go_back());
+
+    nav.open(3);
+    nav.open(4);
+    nav.home();
+    assert_eq!((0, 0), (nav.folder, nav.page));
+    assert!(!nav.can_go_back());
+}
+
+#[test]
+fn test_path_to() {
+    let json = r#"{
+        "name": "Navigation",
+        "description": "",
+        "folders": [
+            {
+                "name": "Home", "id": "Home", "toplevel": true, "immediate": false, "rows": 1, "cols": 2,
+                "buttons": [{"label": "hello"}, {"label": "Animals", "folder": "Home::Animals"}]
+            },
+            {
+                "name": "Other", "id": "Other", "toplevel": true, "immediate": false, "rows": 1, "cols": 1,
+                "buttons": [{"label": "hi"}]
+            },
+            {
+                "name": "Animals", "id": "Home::Animals", "toplevel": false, "immediate": false, "rows": 1, "cols": 2,
+                "buttons": [{"label": "cat"}, {"label": "dog"}, {"label": "fish"}]
+            }
+        ],
+        "hotbar": {"rows": 1, "cols": 1, "buttons": []},
+        "variants": {},
+        "related": {}
+    }"#;
+    let system = System::load_str(json).unwrap();
+
+    assert_eq!(vec![
+        ("Home".to_string(), "Home".to_string()),
+        ("Home::Animals".to_string(), "Animals".to_string()),
+    ], system.breadcrumb(2));
+
+    assert_eq!(Some(vec![
+        Step::SelectFolder { folder: 1 },
+        Step::Press { folder: 1, index: 0 },
+    ]), path_to(&system, 1, 0));
+
+    // "fish" is on the second page of Animals, which is linked from Home.
+    assert_eq!(Some(vec![
+        Step::SelectFolder { folder: 0 },
+        Step::OpenFolder { folder: 0, index: 1 },
+        Step::NextPage { folder: 2, page: 0 },
+        Step::Press { folder: 2, index: 2 },
+    ]), path_to(&system, 2, 2));
+
+    let taps = taps_to_pages(&system);
+    assert_eq!((Some(&1), Some(&3), None), (taps.get(&(1, 0)), taps.get(&(2, 1)), taps.get(&(2, 2))));
+    assert_eq!(path_to(&system, 2, 2).unwrap().len(), taps[&(2, 1)] + 1);
+
+    let mut modeling = Some(Modeling::new("fish".to_string(), path_to(&system, 2, 2).unwrap()));
+    advance(&mut modeling, Step::SelectFolder { folder: 0 });
+    assert_eq!(Some(&Step::OpenFolder { folder: 0, index: 1 }), modeling.as_ref().unwrap().current_step());
+    advance(&mut modeling, Step::SelectFolder { folder: 1 });
+    assert_eq!(None, modeling);
+}
@@ -0,0 +1,401 @@
+//! Printable boards: one PDF page for each page of each folder, laid out like the
+//! board on screen, so the same motor plans work on paper.
+//!
+//! Labels use the PDF's built-in Helvetica, so only Latin-1 characters print;
+//! anything else shows up as "?".
+
+use crate::button::Button;
+use crate::color::{self, Colors};
+use crate::system::System;
+
+use eframe::egui::Color32;
+use log::warn;
+
+use std::collections::HashMap;
+use std::path::Path;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Paper {
+    pub width: f32,
+    pub height: f32,
+}
+
+impl Paper {
+    // Landscape, in points, like the screen.
+    pub const LETTER: Self = Self { width: 792.0, height: 612.0 };
+    pub const A4: Self = Self { width: 842.0, height: 595.0 };
+
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "letter" => Some(Self::LETTER),
+            "a4" => Some(Self::A4),
+            _ => None,
+        }
+    }
+}
+
+const MARGIN: f32 = 36.0;
+const TITLE_SIZE: f32 = 16.0;
+const HINT_SIZE: f32 = 9.0;
+const SPACING: f32 = 4.0;
+
+/// A PDF file, built one object at a time. Object ids start at 1.
+#[derive(Default)]
+struct Writer {
+    objects: Vec<Vec<u8>>,
+}
+
+impl Writer {
+    fn reserve(&mut self) -> usize {
+        self.objects.push(vec![]);
+        self.objects.len()
+    }
+
+    fn set(&mut self, id: usize, object: impl Into<Vec<u8>>) {
+        self.objects[id - 1] = object.into();
+    }
+
+    fn add(&mut self, object: impl Into<Vec<u8>>) -> usize {
+        let id = self.reserve();
+        self.set(id, object);
+        id
+    }
+
+    /// Compresses `data`, which is already encoded with `filters`.
+    fn add_stream(&mut self, dict: &str, filters: &[&str], data: &[u8]) -> usize {
+        let data = miniz_oxide::deflate::compress_to_vec_zlib(data, 6);
+        let filters: Vec<String> = ["FlateDecode"].iter().chain(filters).map(|f| format!("/{}", f)).collect();
+        let mut object = format!("<< {} /Filter [{}] /Length {} >>\nstream\n", dict, filters.join(" "), data.len()).into_bytes();
+        object.extend_from_slice(&data);
+        object.extend_from_slice(b"\nendstream");
+        self.add(object)
+    }
+
+    fn finish(self, root: usize) -> Vec<u8> {
+        let mut out = b"%PDF-1.4\n".to_vec();
+        let mut offsets = vec![];
+        for (i, object) in self.objects.iter().enumerate() {
+            offsets.push(out.len());
+            out.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+            out.extend_from_slice(object);
+            out.extend_from_slice(b"\nendobj\n");
+        }
+
+        let xref = out.len();
+        out.extend_from_slice(format!("xref\n0 {}\n0000000000 65535 f \n", offsets.len() + 1).as_bytes());
+        for offset in offsets {
+            out.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+        }
+        out.extend_from_slice(format!("trailer\n<< /Size {} /Root {} 0 R >>\nstartxref\n{}\n%%EOF\n", self.objects.len() + 1, root, xref).as_bytes());
+        out
+    }
+}
+
+/// A PDF string in WinAnsiEncoding.
+fn pdf_string(text: &str) -> Vec<u8> {
+    let mut out = vec![b'('];
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => out.extend_from_slice(&[b'\\', c as u8]),
+            ' '..='~' | '\u{a0}'..='\u{ff}' => out.push(c as u32 as u8),
+            _ => out.push(b'?'),
+        }
+    }
+    out.push(b')');
+    out
+}
+
+/// Roughly how wide Helvetica text is; there are no font metrics to go on.
+fn text_width(text: &str, size: f32) -> f32 {
+    text.chars().count() as f32 * size * 0.55
+}
+
+fn rgb(color: Color32) -> String {
+    format!("{:.3} {:.3} {:.3}", color.r() as f32 / 255.0, color.g() as f32 / 255.0, color.b() as f32 / 255.0)
+}
+
+/// An image that can go in a PDF as is.
+struct PdfImage {
+    width: usize,
+    height: usize,
+    jpeg: bool,
+    data: Vec<u8>,
+}
+
+fn jpeg_size(data: &[u8]) -> Option<(usize, usize)> {
+    let mut i = 2;
+    while i + 9 < data.len() {
+        if data[i] != 0xff {
+            return None;
+        }
+        let marker = data[i + 1];
+        let length = u16::from_be_bytes([data[i + 2], data[i + 3]]) as usize;
+        // Any start-of-frame marker has the size.
+        if (0xc0..=0xcf).contains(&marker) && ![0xc4, 0xc8, 0xcc].contains(&marker) {
+            let height = u16::from_be_bytes([data[i + 5], data[i + 6]]) as usize;
+            let width = u16::from_be_bytes([data[i + 7], data[i + 8]]) as usize;
+            return Some((width, height));
+        }
+        i += 2 + length;
+    }
+    None
+}
+
+fn load_image(path: &Path) -> Result<PdfImage, Box<dyn std::error::Error>> {
+    let data = std::fs::read(path)?;
+
+    if data.starts_with(&[0xff, 0xd8]) {
+        let (width, height) = jpeg_size(&data).ok_or("invalid JPEG")?;
+        return Ok(PdfImage { width, height, jpeg: true, data });
+    }
+
+    let mut decoder = png::Decoder::new(data.as_slice());
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info()?;
+    let mut pixels = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut pixels)?;
+    pixels.truncate(info.buffer_size());
+
+    // Transparent parts go on white paper.
+    let channels = info.color_type.samples();
+    let mut rgb = Vec::with_capacity(info.width as usize * info.height as usize * 3);
+    for pixel in pixels.chunks_exact(channels) {
+        let (color, alpha) = match pixel {
+            [gray] => ([*gray; 3], 255),
+            [gray, alpha] => ([*gray; 3], *alpha),
+            [r, g, b] => ([*r, *g, *b], 255),
+            [r, g, b, alpha] => ([*r, *g, *b], *alpha),
+            _ => return Err("unsupported PNG".into()),
+        };
+        let alpha = alpha as u32;
+        rgb.extend(color.map(|c| ((c as u32 * alpha + 255 * (255 - alpha)) / 255) as u8));
+    }
+
+    Ok(PdfImage { width: info.width as usize, height: info.height as usize, jpeg: false, data: rgb })
+}
+
+struct Exporter<'a> {
+    system: &'a System,
+    paper: Paper,
+    /// Where relative image paths are relative to.
+    base: Option<&'a Path>,
+    writer: Writer,
+    /// Image path => (XObject id, width, height), or None if it couldn't be loaded.
+    images: HashMap<String, Option<(usize, f32, f32)>>,
+    /// Folder id => the sheet its first page is on, counting from 1.
+    sheets: HashMap<String, usize>,
+}
+
+impl Exporter<'_> {
+    fn image(&mut self, path: &str) -> Option<(usize, f32, f32)> {
+        if let Some(id) = self.images.get(path) {
+            return *id;
+        }
+
+        let full = match self.base {
+            Some(base) => base.join(path),
+            None => Path::new(path).to_path_buf(),
+        };
+        let id = match load_image(&full) {
+            Ok(image) => {
+                let dict = format!("/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceRGB /BitsPerComponent 8", image.width, image.height);
+                let filters: &[&str] = if image.jpeg { &["DCTDecode"] } else { &[] };
+                Some((self.writer.add_stream(&dict, filters, &image.data), image.width as f32, image.height as f32))
+            },
+            Err(err) => {
+                warn!("!!! Could not load image '{}': {}", full.display(), err);
+                None
+            },
+        };
+        self.images.insert(path.to_string(), id);
+        id
+    }
+
+    fn text(&self, out: &mut Vec<u8>, text: &str, font: &str, size: f32, x: f32, y: f32) {
+        if text.is_empty() {
+            return;
+        }
+        out.extend_from_slice(format!("BT /{} {} Tf {:.2} {:.2} Td ", font, size, x, y).as_bytes());
+        out.extend_from_slice(&pdf_string(text));
+        out.extend_from_slice(b" Tj ET\n");
+    }
+
+    /// Draws one cell. `hint` is a second, smaller line, e.g. where a folder link leads.
+    fn cell(&mut self, out: &mut Vec<u8>, rect: (f32, f32, f32, f32), label: &str, hint: Option<&str>, colors: &Colors, image: Option<&str>) {
+        let (x, y, w, h) = rect;
+        let fill = colors.background().unwrap_or(Color32::WHITE);
+        let border = colors.border().unwrap_or(Color32::BLACK);
+        let text = colors.text().unwrap_or(color::text_color_for(fill));
+        out.extend_from_slice(format!("{} rg {} RG 1 w {:.2} {:.2} {:.2} {:.2} re B\n", rgb(fill), rgb(border), x, y, w, h).as_bytes());
+
+        let size = (h * 0.2).min(w * 0.9 / (label.chars().count().max(1) as f32 * 0.55)).min(24.0);
+        let hint_size = size.min(HINT_SIZE);
+        let mut label_y = y + h / 2.0 - size / 3.0;
+
+        if let Some((id, width, height)) = image.and_then(|path| self.image(path)) {
+            // The picture above, the label below.
+            label_y = y + SPACING + hint_size + SPACING;
+            let room_w = w - 2.0 * SPACING;
+            let room_h = h - (label_y - y) - size - 2.0 * SPACING;
+            let scale = (room_w / width).min(room_h / height);
+            if scale > 0.0 {
+                let (iw, ih) = (width * scale, height * scale);
+                let (ix, iy) = (x + (w - iw) / 2.0, y + h - SPACING - ih);
+                out.extend_from_slice(format!("q {:.2} 0 0 {:.2} {:.2} {:.2} cm /Im{} Do Q\n", iw, ih, ix, iy, id).as_bytes());
+            }
+        }
+
+        out.extend_from_slice(format!("{} rg\n", rgb(text)).as_bytes());
+        self.text(out, label, "F1", size, x + (w - text_width(label, size)) / 2.0, label_y);
+        if let Some(hint) = hint {
+            self.text(out, hint, "F1", hint_size, x + (w - text_width(hint, hint_size)) / 2.0, y + SPACING);
+        }
+    }
+
+    fn button(&mut self, out: &mut Vec<u8>, rect: (f32, f32, f32, f32), folder: Option<usize>, button: Option<&Button>, hints: &mut Vec<String>) {
+        let Some(button) = button.filter(|b| !b.hidden) else {
+            let empty = Colors { border: Some("#bbbbbb".to_string()), ..Default::default() };
+            self.cell(out, rect, "", None, &empty, None);
+            return;
+        };
+
+        let colors = self.system.button_colors(folder, button);
+        let hint = button.folder.as_ref().and_then(|id| {
+            let sheet = self.sheets.get(id)?;
+            hints.push(format!("See page {} for {}", sheet, button.label));
+            Some(format!("see page {}", sheet))
+        });
+        self.cell(out, rect, &button.label, hint.as_deref(), &colors, button.image.as_deref());
+    }
+
+    fn sheet(&mut self, folder_idx: usize, page: usize, number: usize, total: usize) -> Vec<u8> {
+        let system = self.system;
+        let folder = &system.folders[folder_idx];
+        let paper = self.paper;
+        let mut out = vec![];
+        let mut hints = vec![];
+
+        out.extend_from_slice(b"0 g\n");
+        let crumbs: Vec<String> = system.breadcrumb(folder_idx).into_iter().map(|(_, name)| name).collect();
+        let mut title = crumbs.join(" > ");
+        if folder.pages() > 1 {
+            title.push_str(&format!(" ({} of {})", page + 1, folder.pages()));
+        }
+        self.text(&mut out, &title, "F2", TITLE_SIZE, MARGIN, paper.height - MARGIN - TITLE_SIZE);
+        // Beside the title rather than in the grid, so it doesn't cover a button.
+        if folder.needs_pagination() {
+            let sheet = self.sheets.get(&folder.id).copied().unwrap_or(1) + folder.next_page(page);
+            let more = format!("More on page {}", sheet);
+            self.text(&mut out, &more, "F1", HINT_SIZE, paper.width - MARGIN - text_width(&more, HINT_SIZE), paper.height - MARGIN - TITLE_SIZE);
+        }
+        let number = format!("Page {} of {}", number, total);
+        self.text(&mut out, &number, "F1", HINT_SIZE, paper.width - MARGIN - text_width(&number, HINT_SIZE), MARGIN - HINT_SIZE);
+
+        // The hotbar gets its own row at the bottom, like on screen.
+        let hotbar = !system.hotbar.buttons.is_empty();
+        let rows = folder.rows + usize::from(hotbar);
+        let top = paper.height - MARGIN - TITLE_SIZE - 2.0 * SPACING;
+        let bottom = MARGIN + 3.0 * HINT_SIZE;
+        let w = (paper.width - 2.0 * MARGIN - SPACING * (folder.cols as f32 - 1.0)) / folder.cols.max(1) as f32;
+        let h = (top - bottom - SPACING * (rows as f32 - 1.0) - if hotbar { SPACING } else { 0.0 }) / rows.max(1) as f32;
+        let rect = |row: usize, col: usize| {
+            let gap = if hotbar && row == folder.rows { SPACING } else { 0.0 };
+            (MARGIN + col as f32 * (w + SPACING), top - (row as f32 + 1.0) * h - row as f32 * SPACING - gap, w, h)
+        };
+
+        for row in 0..folder.rows {
+            for col in 0..folder.cols {
+                let index = folder.index_of(page, col, row);
+                let button = folder.buttons.get(index).and_then(|b| b.as_ref());
+                self.button(&mut out, rect(row, col), Some(folder_idx), button, &mut hints);
+            }
+        }
+
+        if hotbar {
+            for col in 0..folder.cols {
+                self.button(&mut out, rect(folder.rows, col), None, system.hotbar.get_button(folder.cols, 0, col), &mut hints);
+            }
+        }
+
+        hints.dedup();
+        out.extend_from_slice(b"0 g\n");
+        self.text(&mut out, &hints.join("   "), "F1", HINT_SIZE, MARGIN, MARGIN + HINT_SIZE);
+
+        out
+    }
+}
+
+/// A PDF of every page of every folder, in the System's order.
+/// Relative image paths are looked up in `base`, if given.
+pub fn export(system: &System, paper: Paper, base: Option<&Path>) -> Vec<u8> {
+    let mut sheets = HashMap::new();
+    let mut pages = vec![];
+    for (idx, folder) in system.folders.iter().enumerate() {
+        sheets.entry(folder.id.clone()).or_insert(pages.len() + 1);
+        for page in 0..folder.pages() {
+            pages.push((idx, page));
+        }
+    }
+
+    let mut exporter = Exporter { system, paper, base, writer: Writer::default(), images: HashMap::new(), sheets };
+    let catalog = exporter.writer.reserve();
+    let tree = exporter.writer.reserve();
+    let regular = exporter.writer.add("<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>");
+    let bold = exporter.writer.add("<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica-Bold /Encoding /WinAnsiEncoding >>");
+
+    let mut kids = vec![];
+    for (number, (folder, page)) in pages.iter().enumerate() {
+        let content = exporter.sheet(*folder, *page, number + 1, pages.len());
+        let content = exporter.writer.add_stream("", &[], &content);
+
+        // Every image so far, which is more than this page needs, but harmless.
+        let images: Vec<String> = exporter.images.values().flatten().map(|(id, _, _)| format!("/Im{} {} 0 R", id, id)).collect();
+        let page = format!(
+            "<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {} {}] /Resources << /Font << /F1 {} 0 R /F2 {} 0 R >> /XObject << {} >> >> /Contents {} 0 R >>",
+            tree, paper.width, paper.height, regular, bold, images.join(" "), content,
+        );
+        kids.push(exporter.writer.add(page));
+    }
+
+    let kids: Vec<String> = kids.iter().map(|id| format!("{} 0 R", id)).collect();
+    exporter.writer.set(tree, format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids.join(" "), kids.len()));
+    exporter.writer.set(catalog, format!("<< /Type /Catalog /Pages {} 0 R >>", tree));
+    exporter.writer.finish(catalog)
+}
+
+#[test]
+fn test_pdf() {
+    let system = crate::format::from_text("#sgs\nrows = 1\ncols = 2\n:Home\n\t\"hi (there)\"\t\"Animals\" -> Home::Animals\n.\n\
+        :Home::Animals\n\t\"café\"\t\"dog\"\n\t\"猫\"\t\"cat\"\n.\n:hotbar\n\t\"yes\"\n.\n").unwrap();
+    let pdf = export(&system, Paper::LETTER, None);
+    let text = String::from_utf8_lossy(&pdf);
+    let mut content = vec![];
+    let mut rest = &pdf[..];
+    while let Some(start) = rest.windows(10).position(|w| w == b">>\nstream\n") {
+        let length: usize = String::from_utf8_lossy(&rest[..start]).rsplit("/Length ").next().unwrap().trim().parse().unwrap();
+        let data = &rest[start + 10..start + 10 + length];
+        content.extend(miniz_oxide::inflate::decompress_to_vec_zlib(data).unwrap());
+        rest = &rest[start + 10 + length..];
+    }
+    let content_text = String::from_utf8_lossy(&content);
+
+    assert!(text.starts_with("%PDF-1.4"));
+    // Home has one page, and Animals two.
+    assert_eq!(3, text.matches("/Type /Page ").count());
+    assert!(content_text.contains("(see page 2)"));
+    assert!(content_text.contains("(See page 2 for Animals)"));
+    assert!(content_text.contains("(Home > Animals \\(2 of 2\\))"));
+    // "dog" isn't covered by the way to the next page, which wraps around from the last.
+    assert!(content_text.contains("(dog)"));
+    assert!(content_text.contains("(More on page 3)") && content_text.contains("(More on page 2)"));
+    assert!(content_text.contains("(hi \\(there\\))"));
+    assert!(content.windows(6).any(|w| w == b"(caf\xe9)"));
+    assert!(content_text.contains("(?)"));
+
+    // The cross-reference table has to point at the objects.
+    let xref: usize = text.rsplit("startxref\n").next().unwrap().lines().next().unwrap().parse().unwrap();
+    let table = std::str::from_utf8(&pdf[xref..]).unwrap();
+    assert!(table.starts_with("xref"));
+    let first: usize = table.lines().nth(3).unwrap()[..10].parse().unwrap();
+    assert!(pdf[first..].starts_with(b"1 0 obj"));
+}
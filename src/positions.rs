@@ -0,0 +1,152 @@
+use crate::system::{Folder, System};
+
+use std::collections::HashMap;
+use std::fmt;
+
+/// Where a button is drawn. Hotbar buttons are always on row 0.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Position {
+    pub page: usize,
+    pub row: usize,
+    pub col: usize,
+}
+
+impl Position {
+    pub fn in_folder(folder: &Folder, index: usize) -> Self {
+        let (page, col, row) = folder.position_of(index);
+        Self { page, row, col }
+    }
+
+    /// The hotbar pages by the widest folder's columns, and has no "->" button.
+    pub fn in_hotbar(system: &System, index: usize) -> Self {
+        let (_, cols) = system.grid_size();
+        let cols = std::cmp::max(1, cols);
+        Self { page: index / cols, row: 0, col: index % cols }
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "page {}, row {}, column {}", self.page + 1, self.row + 1, self.col + 1)
+    }
+}
+
+/// A button that's somewhere else (or gone) in a newer version of a System.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Move {
+    /// Folder id, or "hotbar".
+    pub folder: String,
+    /// The button's id, or its label if it doesn't have one.
+    pub button: String,
+    pub from: Position,
+    pub to: Option<Position>,
+}
+
+impl fmt::Display for Move {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.to {
+            Some(to) => write!(f, "{}: '{}' moved from {} to {}", self.folder, self.button, self.from, to),
+            None => write!(f, "{}: '{}' at {} was removed", self.folder, self.button, self.from),
+        }
+    }
+}
+
+// Button key => where each button with that key is, in order.
+type Positions = HashMap<String, Vec<Position>>;
+
+fn folder_positions(folder: &Folder) -> Positions {
+    let mut positions: Positions = HashMap::new();
+    for (index, button) in folder.buttons.iter().enumerate() {
+        if let Some(button) = button {
+            positions.entry(button.key().to_string()).or_default().push(Position::in_folder(folder, index));
+        }
+    }
+
+    positions
+}
+
+fn hotbar_positions(system: &System) -> Positions {
+    let mut positions: Positions = HashMap::new();
+    for (index, button) in system.hotbar.buttons.iter().enumerate() {
+        if let Some(button) = button {
+            positions.entry(button.key().to_string()).or_default().push(Position::in_hotbar(system, index));
+        }
+    }
+
+    positions
+}
+
+fn compare(folder: &str, old: Positions, new: Positions, moves: &mut Vec<Move>) {
+    let mut keys: Vec<&String> = old.keys().collect();
+    keys.sort();
+
+    for key in keys {
+        let empty = vec![];
+        let new_positions = new.get(key).unwrap_or(&empty);
+        for (idx, from) in old[key].iter().enumerate() {
+            let to = new_positions.get(idx).copied();
+            if to != Some(*from) {
+                moves.push(Move { folder: folder.to_string(), button: key.clone(), from: *from, to });
+            }
+        }
+    }
+}
+
+/// Lists every button in `old` that isn't in the same place in `new`.
+///
+/// Buttons are matched by id (or label) within each folder. New buttons are fine;
+/// it's only moving or removing ones people already know that hurts.
+pub fn moved_buttons(old: &System, new: &System) -> Vec<Move> {
+    let mut moves = vec![];
+
+    for old_folder in &old.folders {
+        let new_positions = new.folder_index(&old_folder.id)
+            .map(|idx| folder_positions(&new.folders[idx]))
+            .unwrap_or_default();
+        compare(&old_folder.id, folder_positions(old_folder), new_positions, &mut moves);
+    }
+
+    compare("hotbar", hotbar_positions(old), hotbar_positions(new), &mut moves);
+
+    moves
+}
+
+#[test]
+fn test_moved_buttons() {
+    use crate::button::Button;
+
+    let button = |label: &str| Some(Button::new(label.to_string(), None, None));
+    let mut old = System::default();
+    old.folders.push(Folder {
+        id: "Home".to_string(), rows: 2, cols: 2,
+        buttons: vec![button("yes"), button("no"), None, button("more")],
+        ..Default::default()
+    });
+
+    let mut new = old.clone();
+    assert!(moved_buttons(&old, &new).is_empty());
+
+    // Hiding a button keeps everything else where it was.
+    new.folders[0].buttons[1].as_mut().unwrap().hidden = true;
+    assert!(moved_buttons(&old, &new).is_empty());
+
+    // Adding a button never moves one that's already there, even when the folder is full
+    // and starts a new page, or is already on its last.
+    for _ in 0..5 {
+        let before = new.folders[0].clone();
+        new.folders[0].buttons.push(button("stop"));
+        for index in 0..before.buttons.len() {
+            assert_eq!(Position::in_folder(&before, index), Position::in_folder(&new.folders[0], index));
+        }
+        assert!(moved_buttons(&old, &new).is_empty());
+    }
+    assert_eq!(Position { page: 2, row: 0, col: 0 }, Position::in_folder(&new.folders[0], 8));
+
+    // A relabelled button with an id hasn't moved.
+    old.folders[0].buttons[0].as_mut().unwrap().id = Some("yes".to_string());
+    let mut new = old.clone();
+    new.folders[0].buttons[0].as_mut().unwrap().label = "yeah".to_string();
+    new.folders[0].buttons[1] = None;
+    let moves = moved_buttons(&old, &new);
+    assert_eq!(vec!["Home: 'no' at page 1, row 1, column 2 was removed".to_string()], moves.iter().map(|m| m.to_string()).collect::<Vec<_>>());
+}
@@ -0,0 +1,103 @@
+use crate::button::Button;
+use crate::system::System;
+
+use nanoserde::{DeJson, DeJsonErr, SerJson};
+
+use std::collections::HashMap;
+
+/// Which of a folder's buttons a user can see.
+#[derive(Clone, Debug, Default, DeJson, SerJson, PartialEq)]
+pub struct Mask {
+    /// Start from nothing visible, and reveal words one by one.
+    #[nserde(default)]
+    pub hide_all: bool,
+    /// Buttons (by id, or label) that are the other way around.
+    #[nserde(default)]
+    pub except: Vec<String>,
+}
+
+impl Mask {
+    pub fn is_visible(&self, key: &str) -> bool {
+        self.hide_all == self.except.iter().any(|k| k == key)
+    }
+}
+
+/// Everything about one user's copy of a System, kept separately from the System itself.
+#[derive(Clone, Debug, Default, DeJson, SerJson, PartialEq)]
+pub struct Profile {
+    /// Folder id (or "hotbar") => the mask over its buttons. Folders without one show everything.
+    #[nserde(default)]
+    pub masks: HashMap<String, Mask>,
+}
+
+pub const HOTBAR: &str = "hotbar";
+
+impl Profile {
+    pub const STORAGE_KEY: &'static str = "profile";
+
+    pub fn load_str(json: &str) -> Result<Self, DeJsonErr> {
+        DeJson::deserialize_json(json)
+    }
+
+    pub fn load(storage: Option<&dyn eframe::Storage>) -> Self {
+        let json = storage.and_then(|s| s.get_string(Self::STORAGE_KEY));
+        json.and_then(|json| Self::load_str(&json).ok()).unwrap_or_default()
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        storage.set_string(Self::STORAGE_KEY, self.serialize_json());
+    }
+
+    pub fn is_visible(&self, folder: &str, key: &str) -> bool {
+        self.masks.get(folder).map_or(true, |mask| mask.is_visible(key))
+    }
+
+    /// Whether the user can see `button`, in the folder at index `folder`.
+    pub fn shows(&self, system: &System, folder: usize, button: &Button) -> bool {
+        !button.hidden && system.folders.get(folder).map_or(true, |f| self.is_visible(&f.id, button.key()))
+    }
+
+    pub fn toggle(&mut self, folder: &str, key: &str) {
+        let mask = self.masks.entry(folder.to_string()).or_default();
+        if let Some(idx) = mask.except.iter().position(|k| k == key) {
+            mask.except.remove(idx);
+        } else {
+            mask.except.push(key.to_string());
+        }
+
+        if *mask == Mask::default() {
+            self.masks.remove(folder);
+        }
+    }
+
+    pub fn set_all(&mut self, folder: &str, visible: bool) {
+        if visible {
+            self.masks.remove(folder);
+        } else {
+            self.masks.insert(folder.to_string(), Mask { hide_all: true, except: vec![] });
+        }
+    }
+}
+
+#[test]
+fn test_profile() {
+    let mut profile = Profile::default();
+    assert!(profile.is_visible("Nouns", "cat"));
+
+    profile.set_all("Nouns", false);
+    assert!(!profile.is_visible("Nouns", "cat"));
+    assert!(profile.is_visible("Verbs", "go"));
+
+    profile.toggle("Nouns", "cat");
+    assert!(profile.is_visible("Nouns", "cat"));
+    assert!(!profile.is_visible("Nouns", "dog"));
+
+    let json = profile.serialize_json();
+    assert_eq!(profile, Profile::load_str(&json).unwrap());
+
+    // Toggling back to "everything visible" forgets the mask.
+    profile.set_all("Verbs", true);
+    profile.toggle("Verbs", "go");
+    profile.toggle("Verbs", "go");
+    assert!(!profile.masks.contains_key("Verbs"));
+}
@@ -0,0 +1,211 @@
+//! Draws a board to an image without a window or a GPU, e.g. for printing boards
+//! or for comparing screenshots when a System changes.
+//!
+//! The app draws a frame as usual; egui tessellates it into triangles, and we fill
+//! those in on the CPU.
+
+use crate::app::App;
+use crate::settings::Settings;
+use crate::system::System;
+
+use eframe::egui::{self, Color32, Rect, TextureId};
+use eframe::egui::epaint::{textures::TexturesDelta, ClippedPrimitive, ImageData, Mesh, Primitive};
+
+use log::warn;
+
+use std::collections::HashMap;
+
+// Enough for egui to lay out grids and load fonts.
+const MAX_FRAMES: usize = 5;
+
+pub struct Image {
+    pub width: usize,
+    pub height: usize,
+    pub pixels: Vec<Color32>,
+}
+
+struct Texture {
+    width: usize,
+    height: usize,
+    pixels: Vec<Color32>,
+}
+
+impl Texture {
+    fn sample(&self, uv: egui::Pos2) -> Color32 {
+        let x = ((uv.x * self.width as f32) as usize).min(self.width.saturating_sub(1));
+        let y = ((uv.y * self.height as f32) as usize).min(self.height.saturating_sub(1));
+        self.pixels.get(y * self.width + x).copied().unwrap_or(Color32::WHITE)
+    }
+}
+
+fn apply_textures(textures: &mut HashMap<TextureId, Texture>, delta: TexturesDelta) {
+    for (id, delta) in delta.set {
+        let (size, pixels): ([usize; 2], Vec<Color32>) = match &delta.image {
+            ImageData::Color(image) => (image.size, image.pixels.clone()),
+            ImageData::Font(image) => (image.size, image.srgba_pixels(None).collect()),
+        };
+
+        match (delta.pos, textures.get_mut(&id)) {
+            (Some([x0, y0]), Some(texture)) => {
+                for y in 0..size[1] {
+                    for x in 0..size[0] {
+                        if let Some(pixel) = texture.pixels.get_mut((y0 + y) * texture.width + x0 + x) {
+                            *pixel = pixels[y * size[0] + x];
+                        }
+                    }
+                }
+            },
+            _ => {
+                textures.insert(id, Texture { width: size[0], height: size[1], pixels });
+            },
+        }
+    }
+
+    for id in delta.free {
+        textures.remove(&id);
+    }
+}
+
+fn to_rgba(color: Color32) -> [f32; 4] {
+    [color.r(), color.g(), color.b(), color.a()].map(|c| c as f32 / 255.0)
+}
+
+impl Image {
+    fn new(width: usize, height: usize) -> Self {
+        Self { width, height, pixels: vec![Color32::TRANSPARENT; width * height] }
+    }
+
+    fn fill_mesh(&mut self, clip: Rect, mesh: &Mesh, texture: Option<&Texture>) {
+        let clip = clip.intersect(Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(self.width as f32, self.height as f32)));
+        if !clip.is_positive() {
+            return;
+        }
+
+        for triangle in mesh.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|i| &mesh.vertices[triangle[i] as usize]);
+            let edge = |p: egui::Pos2, q: egui::Pos2, r: egui::Pos2| (q.x - p.x) * (r.y - p.y) - (q.y - p.y) * (r.x - p.x);
+            let area = edge(a.pos, b.pos, c.pos);
+            if area == 0.0 {
+                continue;
+            }
+
+            let bounds = Rect::from_points(&[a.pos, b.pos, c.pos]).intersect(clip);
+            if !bounds.is_positive() {
+                continue;
+            }
+            let colors = [a, b, c].map(|v| to_rgba(v.color));
+
+            for y in (bounds.min.y.floor() as usize)..(bounds.max.y.ceil() as usize) {
+                for x in (bounds.min.x.floor() as usize)..(bounds.max.x.ceil() as usize) {
+                    // Pixel centers, like a GPU would.
+                    let p = egui::pos2(x as f32 + 0.5, y as f32 + 0.5);
+                    let weights = [edge(b.pos, c.pos, p) / area, edge(c.pos, a.pos, p) / area, edge(a.pos, b.pos, p) / area];
+                    if weights.iter().any(|w| *w < 0.0) || !clip.contains(p) {
+                        continue;
+                    }
+
+                    let mut src = [0.0; 4];
+                    for (color, weight) in colors.iter().zip(weights) {
+                        for (s, c) in src.iter_mut().zip(color) {
+                            *s += c * weight;
+                        }
+                    }
+                    if let Some(texture) = texture {
+                        let uv = egui::pos2(
+                            a.uv.x * weights[0] + b.uv.x * weights[1] + c.uv.x * weights[2],
+                            a.uv.y * weights[0] + b.uv.y * weights[1] + c.uv.y * weights[2],
+                        );
+                        let texel = to_rgba(texture.sample(uv));
+                        for (s, t) in src.iter_mut().zip(texel) {
+                            *s *= t;
+                        }
+                    }
+
+                    // Everything is premultiplied, so this is "over".
+                    let dst = to_rgba(self.pixels[y * self.width + x]);
+                    let out: Vec<u8> = src.iter().zip(dst).map(|(s, d)| ((s + d * (1.0 - src[3])) * 255.0).round().clamp(0.0, 255.0) as u8).collect();
+                    self.pixels[y * self.width + x] = Color32::from_rgba_premultiplied(out[0], out[1], out[2], out[3]);
+                }
+            }
+        }
+    }
+
+    pub fn to_png(&self) -> Result<Vec<u8>, Box<dyn std::error::Error>> {
+        let mut data = vec![];
+        let mut encoder = png::Encoder::new(&mut data, self.width as u32, self.height as u32);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let rgba: Vec<u8> = self.pixels.iter().flat_map(|p| p.to_srgba_unmultiplied()).collect();
+        encoder.write_header()?.write_image_data(&rgba)?;
+
+        Ok(data)
+    }
+}
+
+/// Draws `page` of the folder with id `folder`, as the app would on a `width` by `height` screen.
+pub fn render(system: &System, folder: &str, page: usize, width: usize, height: usize, settings: Settings) -> Result<Image, String> {
+    let folder_idx = system.folder_index(folder).ok_or_else(|| format!("No folder with id '{}'.", folder))?;
+    let pages = system.folders[folder_idx].pages();
+    if page >= pages {
+        return Err(format!("'{}' only has {} page(s).", folder, pages));
+    }
+    if width == 0 || height == 0 {
+        return Err("The image needs a width and height.".to_string());
+    }
+
+    let ctx = egui::Context::default();
+    let mut app = App::headless(&ctx, system.clone(), settings);
+    app.show_page(folder_idx, page);
+
+    let input = egui::RawInput {
+        screen_rect: Some(Rect::from_min_size(egui::Pos2::ZERO, egui::vec2(width as f32, height as f32))),
+        pixels_per_point: Some(1.0),
+        ..Default::default()
+    };
+
+    let mut textures = HashMap::new();
+    let mut output = ctx.run(input.clone(), |ctx| app.draw(ctx));
+    for _ in 1..MAX_FRAMES {
+        if !output.repaint_after.is_zero() {
+            break;
+        }
+        apply_textures(&mut textures, std::mem::take(&mut output.textures_delta));
+        output = ctx.run(input.clone(), |ctx| app.draw(ctx));
+    }
+    apply_textures(&mut textures, output.textures_delta);
+
+    let mut image = Image::new(width, height);
+    for ClippedPrimitive { clip_rect, primitive } in ctx.tessellate(output.shapes) {
+        match primitive {
+            Primitive::Mesh(mesh) => {
+                let texture = textures.get(&mesh.texture_id);
+                if texture.is_none() {
+                    warn!("!!! Missing texture {:?} while rendering.", mesh.texture_id);
+                }
+                image.fill_mesh(clip_rect, &mesh, texture);
+            },
+            Primitive::Callback(_) => warn!("!!! Can't render paint callbacks without a GPU."),
+        }
+    }
+
+    Ok(image)
+}
+
+#[test]
+fn test_render() {
+    let system = crate::format::from_text("#sgs\nrows = 2\ncols = 2\n:Home\n\t\"yes\"\t\"no\"\n\t\"More\" -> Home::More\n.\n\
+        :Home::More\n\t\"a\"\t\"b\"\n\t\"c\"\t\"d\"\n\t\"e\"\n.\n:hotbar\n\t\"hi\"\n.\n").unwrap();
+
+    assert!(render(&system, "Nope", 0, 640, 480, Settings::default()).is_err());
+    assert!(render(&system, "Home", 1, 640, 480, Settings::default()).is_err());
+
+    let image = render(&system, "Home::More", 1, 640, 480, Settings::default()).unwrap();
+    assert_eq!(640 * 480, image.pixels.len());
+    // The panel background covers everything, and something (labels, borders) is drawn on it.
+    assert!(image.pixels.iter().all(|p| p.a() == 255));
+    assert!(image.pixels.iter().any(|p| *p != image.pixels[0]));
+
+    let png = image.to_png().unwrap();
+    assert_eq!(b"\x89PNG", &png[0..4]);
+}
@@ -0,0 +1,304 @@
+//! `sgs report`: what an activity log (see `lam`) says about how a System is used,
+//! to help decide what to move to the hotbar or a top-level folder.
+
+use crate::lam::{self, Record};
+use crate::system::System;
+
+use std::collections::{BTreeMap, BTreeSet};
+
+/// How many of the most-used words to list.
+const MOST_USED: usize = 25;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    Markdown,
+    Html,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name {
+            "md" | "markdown" => Some(Self::Markdown),
+            "html" => Some(Self::Html),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Report {
+    pub system: String,
+    /// How often each word was used, lowercased. Related words and variants count as
+    /// the word they turned into.
+    pub words: BTreeMap<String, usize>,
+    /// (date, different words used) for each day with any activity.
+    pub days: Vec<(String, usize)>,
+    /// The same, for weeks starting on Monday.
+    pub weeks: Vec<(String, usize)>,
+    pub utterances: usize,
+    /// Words in all utterances, for the mean length of utterance.
+    pub utterance_words: usize,
+    /// Words added to the panel, from anywhere.
+    pub base: usize,
+    pub related: usize,
+    pub variants: usize,
+    /// Every button press, including opening folders.
+    pub taps: usize,
+    /// Words in the System that were never used.
+    pub never_used: Vec<String>,
+}
+
+enum Press {
+    Word(String),
+    Related(String),
+    Variant(String),
+    // Opening a folder, or a button with an action.
+    Other,
+}
+
+fn classify(system: &System, record: &Record) -> Press {
+    let label = record.text.to_lowercase();
+    match record.source.as_str() {
+        "related" => Press::Related(label),
+        "variants" => Press::Variant(label),
+        "board" => {
+            let button = record.folder.as_deref()
+                .and_then(|id| system.folder_index(id))
+                .and_then(|folder| system.folders[folder].buttons.get(record.cell?)?.as_ref())
+                .filter(|b| b.label == record.text);
+            // If the System changed since, assume it was a word.
+            match button {
+                Some(b) if b.folder.is_some() || b.action.is_some() => Press::Other,
+                _ => Press::Word(label),
+            }
+        },
+        "hotbar" if system.hotbar.buttons.iter().flatten().any(|b| b.label == record.text && b.action.is_some()) => Press::Other,
+        _ => Press::Word(label),
+    }
+}
+
+// The Monday starting the week `millis` is in. 1970-01-01 was a Thursday, so its week
+// (e.g. a zeroed record's) starts on 1970-01-01 instead, as nothing goes earlier.
+fn week_start(millis: u64) -> u64 {
+    let days = millis / 1000 / 86400;
+    let monday = days.saturating_sub((days + 3) % 7);
+    monday * 86400 * 1000
+}
+
+impl Report {
+    pub fn new(system: &System, records: &[Record]) -> Self {
+        let mut report = Self { system: system.name.clone(), ..Default::default() };
+        let mut days: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        let mut weeks: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+
+        for record in records {
+            if record.source == "spoken" {
+                let words = record.text.split_whitespace().count();
+                if words > 0 {
+                    report.utterances += 1;
+                    report.utterance_words += words;
+                }
+                continue;
+            }
+
+            report.taps += 1;
+            let word = match classify(system, record) {
+                Press::Word(word) => { report.base += 1; word },
+                Press::Related(word) => { report.related += 1; word },
+                Press::Variant(word) => { report.variants += 1; word },
+                Press::Other => continue,
+            };
+            *report.words.entry(word.clone()).or_default() += 1;
+            days.entry(lam::date_time(record.time).0).or_default().insert(word.clone());
+            weeks.entry(lam::date_time(week_start(record.time)).0).or_default().insert(word);
+        }
+        report.days = days.into_iter().map(|(day, words)| (day, words.len())).collect();
+        report.weeks = weeks.into_iter().map(|(week, words)| (week, words.len())).collect();
+
+        let buttons = system.folders.iter().flat_map(|f| f.buttons.iter().flatten()).chain(system.hotbar.buttons.iter().flatten());
+        let vocabulary: BTreeSet<String> = buttons
+            .filter(|b| b.folder.is_none() && b.action.is_none())
+            .map(|b| b.label.to_lowercase())
+            .collect();
+        report.never_used = vocabulary.into_iter().filter(|word| !report.words.contains_key(word)).collect();
+
+        report
+    }
+
+    pub fn most_used(&self) -> Vec<(&str, usize)> {
+        let mut words: Vec<_> = self.words.iter().map(|(word, count)| (word.as_str(), *count)).collect();
+        // Most used first, then alphabetically.
+        words.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+        words.truncate(MOST_USED);
+        words
+    }
+
+    pub fn mean_length_of_utterance(&self) -> f32 {
+        ratio(self.utterance_words, self.utterances)
+    }
+
+    /// Button presses for each word added to the panel, including the taps spent
+    /// opening folders and picking related words or variants.
+    pub fn taps_per_word(&self) -> f32 {
+        ratio(self.taps, self.base)
+    }
+
+    fn sections(&self) -> Vec<Section> {
+        let words: usize = self.words.values().sum();
+        let percent = |n: usize| format!("{:.0}%", 100.0 * ratio(n, words));
+
+        vec![
+            Section {
+                title: "Summary".to_string(),
+                text: vec![
+                    format!("{} word(s) used, {} of them different.", words, self.words.len()),
+                    format!("Mean length of utterance: {:.2} words, over {} utterance(s).", self.mean_length_of_utterance(), self.utterances),
+                    format!("Navigation cost: {:.2} taps per word, over {} tap(s).", self.taps_per_word(), self.taps),
+                ],
+                table: None,
+            },
+            Section {
+                title: "Most-used words".to_string(),
+                text: vec!["Candidates for the hotbar or a top-level folder.".to_string()],
+                table: Some((vec!["Word", "Uses"], self.most_used().into_iter().map(|(w, n)| vec![w.to_string(), n.to_string()]).collect())),
+            },
+            Section {
+                title: "Different words per day".to_string(),
+                text: vec![],
+                table: Some((vec!["Day", "Words"], self.days.iter().map(|(d, n)| vec![d.clone(), n.to_string()]).collect())),
+            },
+            Section {
+                title: "Different words per week".to_string(),
+                text: vec![],
+                table: Some((vec!["Week of", "Words"], self.weeks.iter().map(|(d, n)| vec![d.clone(), n.to_string()]).collect())),
+            },
+            Section {
+                title: "Word forms".to_string(),
+                text: vec!["How words were picked: from a button, or changed using the related and variant columns.".to_string()],
+                table: Some((vec!["From", "Words", "Share"], vec![
+                    vec!["Base word".to_string(), self.base.to_string(), percent(self.base)],
+                    vec!["Related".to_string(), self.related.to_string(), percent(self.related)],
+                    vec!["Variants".to_string(), self.variants.to_string(), percent(self.variants)],
+                ])),
+            },
+            Section {
+                title: format!("Never used ({})", self.never_used.len()),
+                text: vec![self.never_used.join(", ")],
+                table: None,
+            },
+        ]
+    }
+
+    pub fn to_markdown(&self) -> String {
+        let escape = |s: &str| s.replace('|', "\\|");
+        let mut out = format!("# Usage report: {}\n", self.system);
+        for section in self.sections() {
+            out += &format!("\n## {}\n\n", section.title);
+            for line in &section.text {
+                out += &format!("{}\n", line);
+            }
+            if let Some((headers, rows)) = &section.table {
+                if !section.text.is_empty() {
+                    out += "\n";
+                }
+                out += &format!("| {} |\n", headers.join(" | "));
+                out += &format!("|{}\n", "---|".repeat(headers.len()));
+                for row in rows {
+                    out += &format!("| {} |\n", row.iter().map(|cell| escape(cell)).collect::<Vec<_>>().join(" | "));
+                }
+            }
+        }
+        out
+    }
+
+    pub fn to_html(&self) -> String {
+        let escape = |s: &str| s.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;");
+        let title = format!("Usage report: {}", escape(&self.system));
+        let mut out = format!("<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n</head>\n<body>\n<h1>{}</h1>\n", title, title);
+        for section in self.sections() {
+            out += &format!("<h2>{}</h2>\n", escape(&section.title));
+            for line in &section.text {
+                out += &format!("<p>{}</p>\n", escape(line));
+            }
+            if let Some((headers, rows)) = &section.table {
+                out += "<table>\n<tr>";
+                for header in headers {
+                    out += &format!("<th>{}</th>", header);
+                }
+                out += "</tr>\n";
+                for row in rows {
+                    out += "<tr>";
+                    for cell in row {
+                        out += &format!("<td>{}</td>", escape(cell));
+                    }
+                    out += "</tr>\n";
+                }
+                out += "</table>\n";
+            }
+        }
+        out + "</body>\n</html>\n"
+    }
+
+    pub fn render(&self, format: Format) -> String {
+        match format {
+            Format::Markdown => self.to_markdown(),
+            Format::Html => self.to_html(),
+        }
+    }
+}
+
+struct Section {
+    title: String,
+    text: Vec<String>,
+    table: Option<(Vec<&'static str>, Vec<Vec<String>>)>,
+}
+
+fn ratio(n: usize, of: usize) -> f32 {
+    if of == 0 { 0.0 } else { n as f32 / of as f32 }
+}
+
+#[test]
+fn test_report() {
+    let system = crate::format::from_text("#sgs\nname = \"Report\"\nrows = 1\ncols = 3\n\
+        :Home\n\t\"I\"\t\"want\"\t\"Food\" -> Home::Food\n.\n\
+        :Home::Food\n\t\"apple\"\t\"cookie\"\n.\n").unwrap();
+    let press = |day: u64, source: &str, folder: Option<&str>, cell: Option<usize>, text: &str| Record {
+        // Noon on 2024-03-03 (a Sunday), plus `day` days.
+        time: (1709467200 + day * 86400) * 1000,
+        source: source.to_string(),
+        folder: folder.map(str::to_string),
+        page: None,
+        cell,
+        text: text.to_string(),
+    };
+    let records = vec![
+        press(0, "board", Some("Home"), Some(0), "I"),
+        press(0, "board", Some("Home"), Some(1), "want"),
+        press(0, "board", Some("Home"), Some(2), "Food"),
+        press(0, "board", Some("Home::Food"), Some(1), "cookie"),
+        press(0, "variants", None, Some(1), "cookies"),
+        press(0, "spoken", None, None, "I want cookies"),
+        press(1, "keyboard", None, None, "want"),
+        press(1, "spoken", None, None, "want"),
+    ];
+    let report = Report::new(&system, &records);
+
+    assert_eq!(vec![("want", 2), ("cookie", 1), ("cookies", 1), ("i", 1)], report.most_used());
+    assert_eq!(vec![("2024-03-03".to_string(), 4), ("2024-03-04".to_string(), 1)], report.days);
+    // Sunday is the end of the week before.
+    assert_eq!(vec![("2024-02-26".to_string(), 4), ("2024-03-04".to_string(), 1)], report.weeks);
+    assert_eq!((4, 0, 1), (report.base, report.related, report.variants));
+    assert_eq!(2.0, report.mean_length_of_utterance());
+    assert_eq!(6.0 / 4.0, report.taps_per_word());
+    assert_eq!(vec!["apple"], report.never_used);
+
+    // A zeroed record, from the first days of 1970, doesn't have a week before it to go in.
+    let zeroed = Record { time: 0, ..press(0, "board", Some("Home"), Some(1), "want") };
+    assert_eq!(vec![("1970-01-01".to_string(), 1)], Report::new(&system, &[zeroed]).weeks);
+    assert_eq!(4 * 86400 * 1000, week_start(4 * 86400 * 1000));
+
+    let markdown = report.to_markdown();
+    assert!(markdown.starts_with("# Usage report: Report\n"));
+    assert!(markdown.contains("| want | 2 |\n"));
+    assert!(report.to_html().contains("<tr><td>Variants</td><td>1</td><td>20%</td></tr>"));
+}
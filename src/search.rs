@@ -0,0 +1,171 @@
+use crate::button::Button;
+use crate::system::System;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MatchKind {
+    Label,
+    Related,
+    Variant,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchResult {
+    pub label: String,
+    pub kind: MatchKind,
+    pub folder: usize,
+    pub index: usize,
+    pub related: Option<usize>,
+    pub variant: Option<usize>,
+}
+
+impl SearchResult {
+    /// The panel entry for this result, with related/variant already applied.
+    pub fn button(&self, system: &System) -> Option<Button> {
+        let mut button = system.folders.get(self.folder)?.buttons.get(self.index)?.clone()?;
+        if let Some(related) = self.related {
+            button.set_related(related);
+        }
+        if let Some(variant) = self.variant {
+            button.set_variant(variant);
+        }
+
+        Some(button)
+    }
+}
+
+#[derive(Default)]
+pub struct SearchIndex {
+    entries: Vec<(String, SearchResult)>,
+}
+
+impl SearchIndex {
+    pub fn new(system: &System) -> Self {
+        let mut index = Self::default();
+
+        for (folder_idx, folder) in system.folders.iter().enumerate() {
+            for (button_idx, button) in folder.buttons.iter().enumerate() {
+                let Some(button) = button else { continue };
+
+                // Folder links and actions aren't words, and hidden buttons can't be reached.
+                if button.folder.is_some() || button.action.is_some() || button.hidden {
+                    continue;
+                }
+
+                let mut add = |label: &str, kind, related, variant| {
+                    let key = label.to_lowercase();
+                    let duplicate = index.entries.iter().rev()
+                        .take_while(|(_, r)| r.folder == folder_idx && r.index == button_idx)
+                        .any(|(k, _)| *k == key);
+                    if !duplicate {
+                        let result = SearchResult { label: label.to_string(), kind, folder: folder_idx, index: button_idx, related, variant };
+                        index.entries.push((key, result));
+                    }
+                };
+
+                add(&button.label, MatchKind::Label, None, None);
+
+                if let Some(variants) = system.variants.get(&button.label) {
+                    for (variant_idx, variant) in variants.iter().enumerate() {
+                        add(&variant.label, MatchKind::Variant, None, Some(variant_idx));
+                    }
+                }
+
+                if let Some(relateds) = system.related.get(&button.label) {
+                    for (related_idx, related) in relateds.iter().enumerate() {
+                        add(&related.label, MatchKind::Related, Some(related_idx), None);
+
+                        if let Some(variants) = system.variants.get(&related.label) {
+                            for (variant_idx, variant) in variants.iter().enumerate() {
+                                add(&variant.label, MatchKind::Variant, Some(related_idx), Some(variant_idx));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        index
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Exact matches first, then prefix matches, then anything containing `query`.
+    pub fn search(&self, query: &str) -> Vec<&SearchResult> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return vec![];
+        }
+
+        let mut matches: Vec<(usize, &String, &SearchResult)> = self.entries.iter()
+            .filter_map(|(key, result)| {
+                let rank =
+                    if *key == query {
+                        0
+                    } else if key.starts_with(&query) {
+                        1
+                    } else if key.contains(&query) {
+                        2
+                    } else {
+                        return None;
+                    };
+                Some((rank, key, result))
+            })
+            .collect();
+
+        // Stable sort, so equally-ranked results stay in folder order.
+        matches.sort_by_key(|(rank, key, _)| (*rank, key.len()));
+        matches.into_iter().map(|(_, _, result)| result).collect()
+    }
+}
+
+#[test]
+fn test_search() {
+    let json = r#"{
+        "name": "Search",
+        "description": "",
+        "folders": [
+            {
+                "name": "Home", "id": "Home", "toplevel": true, "immediate": false, "rows": 1, "cols": 2,
+                "buttons": [{"label": "Animals", "folder": "Home::Animals"}, {"label": "hello"}]
+            },
+            {
+                "name": "Animals", "id": "Home::Animals", "toplevel": false, "immediate": false, "rows": 1, "cols": 2,
+                "buttons": [{"label": "cat"}, {"label": "dog"}, {"label": "catfish"}]
+            }
+        ],
+        "hotbar": {"rows": 1, "cols": 1, "buttons": []},
+        "variants": {
+            "hi": [{"label": "hi"}, {"label": "hiya"}]
+        },
+        "related": {
+            "hello": [{"label": "hello"}, {"label": "hi"}]
+        }
+    }"#;
+    let system = System::load_str(json).unwrap();
+    let index = SearchIndex::new(&system);
+
+    assert!(index.search("animals").is_empty());
+    assert!(index.search("  ").is_empty());
+
+    let results = index.search("CAT");
+    assert_eq!(vec!["cat", "catfish"], results.iter().map(|r| r.label.as_str()).collect::<Vec<_>>());
+    assert_eq!((1, 0), (results[0].folder, results[0].index));
+    // Two buttons per page.
+    assert_eq!((1, 0, 0), system.folders[1].position_of(results[1].index));
+
+    let results = index.search("hiya");
+    assert_eq!(1, results.len());
+    assert_eq!(MatchKind::Variant, results[0].kind);
+    let button = results[0].button(&system).unwrap();
+    assert_eq!("hello", button.label);
+    assert_eq!("hiya", button.get_label(&system));
+
+    // "hello" is listed as related to itself; it shouldn't show up twice.
+    assert_eq!(1, index.search("hello").len());
+}
@@ -0,0 +1,96 @@
+use crate::layout::Layout;
+
+use nanoserde::{DeJson, DeJsonErr, SerJson};
+
+/// Per-user preferences, kept in eframe's storage between runs.
+#[derive(Clone, Debug, DeJson, SerJson, PartialEq)]
+pub struct Settings {
+    /// Speak each word as it's added to the panel.
+    #[nserde(default)]
+    pub speak_each_word: bool,
+    #[nserde(default = "true")]
+    pub clear_after_speaking: bool,
+    /// Name or id of the preferred voice, if any.
+    pub voice: Option<String>,
+    /// How fast and how high to speak. 1.0 is the voice's normal.
+    #[nserde(default = "1.0")]
+    pub rate: f32,
+    #[nserde(default = "1.0")]
+    pub pitch: f32,
+    #[nserde(default = "default")]
+    pub theme: String,
+    /// Language for the app's own buttons and messages, e.g. "es".
+    #[nserde(default = "en")]
+    pub locale: String,
+    /// Overrides the System's layout.
+    pub layout: Option<Layout>,
+    /// Needed to open the editor, if set. This keeps it from being opened by accident;
+    /// it isn't meant as real security.
+    pub pin: Option<String>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            speak_each_word: false,
+            clear_after_speaking: true,
+            voice: None,
+            rate: 1.0,
+            pitch: 1.0,
+            theme: "default".to_string(),
+            locale: "en".to_string(),
+            layout: None,
+            pin: None,
+        }
+    }
+}
+
+impl Settings {
+    pub const STORAGE_KEY: &'static str = "settings";
+
+    pub fn load_str(json: &str) -> Result<Self, DeJsonErr> {
+        DeJson::deserialize_json(json)
+    }
+
+    pub fn load(storage: Option<&dyn eframe::Storage>) -> Self {
+        let json = storage.and_then(|s| s.get_string(Self::STORAGE_KEY));
+        json.and_then(|json| Self::load_str(&json).ok()).unwrap_or_default()
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        storage.set_string(Self::STORAGE_KEY, self.serialize_json());
+    }
+
+    fn toggle_mut(&mut self, name: &str) -> Option<&mut bool> {
+        match name {
+            "speak_each_word" => Some(&mut self.speak_each_word),
+            "clear_after_speaking" => Some(&mut self.clear_after_speaking),
+            _ => None,
+        }
+    }
+
+    /// Flips the on/off setting called `name`. Returns false if there's no such setting.
+    pub fn toggle(&mut self, name: &str) -> bool {
+        if let Some(value) = self.toggle_mut(name) {
+            *value = !*value;
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[test]
+fn test_settings() {
+    // Settings saved by older versions may be missing fields.
+    let settings = Settings::load_str("{}").unwrap();
+    assert_eq!(Settings::default(), settings);
+
+    let mut settings = Settings::default();
+    assert!(settings.toggle("clear_after_speaking"));
+    assert!(!settings.clear_after_speaking);
+    assert!(!settings.toggle("no_such_setting"));
+
+    let json = settings.serialize_json();
+    assert_eq!(settings, Settings::load_str(&json).unwrap());
+}
@@ -1,38 +1,147 @@
-use tts::Tts;
 use std::ops::Deref;
 
 // TODO: Integrate macOS stuff, if I ever get a Mac to test it on. https://github.com/ndarilek/tts-rs/blob/master/examples/hello_world.rs
 
+#[cfg(not(target_arch = "wasm32"))]
+type Backend = tts::Tts;
+
+// The browser's own speech, which has voices the page can list and tells us when it's done.
+#[cfg(target_arch = "wasm32")]
+type Backend = crate::web_speech::WebSpeech;
+
 pub struct SpeechEngine {
-    pub tts: Tts,
+    // None for a silent engine.
+    pub tts: Option<Backend>,
 }
 
 impl SpeechEngine {
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn default() -> Result<Self, Box<dyn std::error::Error>> {
+        let tts = tts::Tts::default()?;
+
+        Ok(SpeechEngine { tts: Some(tts) })
+    }
+
+    #[cfg(target_arch = "wasm32")]
     pub fn default() -> Result<Self, Box<dyn std::error::Error>> {
-        let tts = Tts::default()?;
+        let tts = crate::web_speech::WebSpeech::browser()?;
+
+        Ok(SpeechEngine { tts: Some(tts) })
+    }
 
-        Ok(SpeechEngine { tts })
+    /// An engine that never says anything, e.g. for rendering boards without a GUI.
+    pub fn silent() -> Self {
+        SpeechEngine { tts: None }
     }
 
 
     pub fn speak<S: Into<String> + Deref<Target=str>>(&mut self, text: S) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(tts) = &mut self.tts else { return Ok(()) };
         if text.len() == 0 {
             return Ok(());
         }
 
-        // true = interrupt current speech; false = don't interrupt current speech.
-        let interrupt = false;
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            // true = interrupt current speech; false = don't interrupt current speech.
+            let interrupt = false;
+
+            tts.speak(text, interrupt)?;
+        }
+        #[cfg(target_arch = "wasm32")]
+        tts.speak(&text)?;
 
-        self.tts.speak(text, interrupt)?;
         Ok(())
     }
 
+    /// Names of the voices to choose from. Empty for a silent engine, or on the web
+    /// until the browser has loaded its voices.
+    pub fn voices(&self) -> Vec<String> {
+        let Some(tts) = &self.tts else { return vec![] };
+
+        #[cfg(not(target_arch = "wasm32"))]
+        { tts.voices().map(|voices| voices.iter().map(|v| v.name()).collect()).unwrap_or_default() }
+
+        #[cfg(target_arch = "wasm32")]
+        tts.voices()
+    }
+
+    /// Switches to the first voice whose name or id matches `voice`, ignoring case.
+    pub fn set_voice(&mut self, voice: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(tts) = &mut self.tts else { return Err("Speech is disabled".into()) };
+
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            let voices = tts.voices()?;
+            let found = voices.iter().find(|v| v.name().eq_ignore_ascii_case(voice) || v.id().eq_ignore_ascii_case(voice));
+            if let Some(found) = found {
+                tts.set_voice(found)?;
+                Ok(())
+            } else {
+                Err(format!("No voice named '{}'", voice).into())
+            }
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        Ok(tts.set_voice(voice)?)
+    }
+
+    /// Sets how fast to speak. 1.0 is normal, 2.0 twice as fast.
+    pub fn set_rate(&mut self, rate: f32) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(tts) = &mut self.tts else { return Ok(()) };
+
+        // Each tts backend has its own range, but "normal" is always somewhere in it.
+        #[cfg(not(target_arch = "wasm32"))]
+        tts.set_rate((tts.normal_rate() * rate).clamp(tts.min_rate(), tts.max_rate()))?;
+
+        #[cfg(target_arch = "wasm32")]
+        { tts.rate = rate; }
+
+        Ok(())
+    }
+
+    /// Sets how high to speak. 1.0 is normal.
+    pub fn set_pitch(&mut self, pitch: f32) -> Result<(), Box<dyn std::error::Error>> {
+        let Some(tts) = &mut self.tts else { return Ok(()) };
+
+        #[cfg(not(target_arch = "wasm32"))]
+        tts.set_pitch((tts.normal_pitch() * pitch).clamp(tts.min_pitch(), tts.max_pitch()))?;
+
+        #[cfg(target_arch = "wasm32")]
+        { tts.pitch = pitch; }
+
+        Ok(())
+    }
+
+    /// Calls `callback` when speech ends (and on the web, when voices are loaded),
+    /// so the app can redraw without polling. Not every tts backend supports this.
+    pub fn on_change(&mut self, callback: impl Fn() + 'static) {
+        let Some(tts) = &mut self.tts else { return };
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if tts.on_utterance_end(Some(Box::new(move |_| callback()))).is_err() {
+            log::info!("This tts backend doesn't say when speech ends.");
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        tts.on_change(callback);
+    }
+
     pub fn stop(&mut self) {
-        let _ = self.tts.stop();
+        if let Some(tts) = &mut self.tts {
+            #[allow(clippy::let_unit_value)]
+            let _ = tts.stop();
+        }
     }
 
     pub fn is_speaking(&mut self) -> bool {
-        // Questionable life choice: Assume not speaking if there's an error.
-        self.tts.is_speaking().unwrap_or(false)
+        #[cfg(not(target_arch = "wasm32"))]
+        {
+            // Questionable life choice: Assume not speaking if there's an error.
+            self.tts.as_ref().is_some_and(|tts| tts.is_speaking().unwrap_or(false))
+        }
+
+        #[cfg(target_arch = "wasm32")]
+        self.tts.as_mut().is_some_and(|tts| tts.is_speaking())
     }
 }
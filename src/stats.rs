@@ -0,0 +1,104 @@
+use crate::system::System;
+
+use std::collections::BTreeMap;
+use std::fmt;
+
+/// How many examples to list for duplicates and missing pronunciations.
+const EXAMPLES: usize = 20;
+
+/// Counts for `sgs stats`.
+#[derive(Debug, Default)]
+pub struct Stats {
+    pub name: String,
+    /// (folder id, buttons, pages) for each folder.
+    pub folders: Vec<(String, usize, usize)>,
+    pub toplevel: usize,
+    pub hotbar: usize,
+    /// Word labels that appear more than once, and the folders they're in.
+    pub duplicates: BTreeMap<String, Vec<String>>,
+    /// Words (not folder links or actions) with no pronunciation.
+    pub words: usize,
+    pub unpronounced: Vec<String>,
+}
+
+impl Stats {
+    pub fn new(system: &System) -> Self {
+        let mut stats = Self {
+            name: system.name.clone(),
+            toplevel: system.toplevel_folders().len(),
+            hotbar: system.hotbar.buttons.iter().flatten().count(),
+            ..Default::default()
+        };
+
+        let mut places: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for folder in &system.folders {
+            stats.folders.push((folder.id.clone(), folder.buttons.iter().flatten().count(), folder.pages()));
+
+            for button in folder.buttons.iter().flatten() {
+                if button.folder.is_some() || button.action.is_some() {
+                    continue;
+                }
+                stats.words += 1;
+                places.entry(button.label.clone()).or_default().push(folder.id.clone());
+                if button.pronunciation.is_none() {
+                    stats.unpronounced.push(button.label.clone());
+                }
+            }
+        }
+        stats.duplicates = places.into_iter().filter(|(_, folders)| folders.len() > 1).collect();
+
+        stats
+    }
+
+    pub fn buttons(&self) -> usize {
+        self.folders.iter().map(|(_, buttons, _)| buttons).sum()
+    }
+}
+
+fn more(f: &mut fmt::Formatter, total: usize) -> fmt::Result {
+    if total > EXAMPLES {
+        writeln!(f, "    ...and {} more", total - EXAMPLES)?;
+    }
+    Ok(())
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "System: {}", self.name)?;
+        writeln!(f, "Folders: {} ({} top-level)", self.folders.len(), self.toplevel)?;
+        writeln!(f, "Buttons: {} ({} words), plus {} in the hotbar", self.buttons(), self.words, self.hotbar)?;
+        writeln!(f)?;
+
+        for (id, buttons, pages) in &self.folders {
+            writeln!(f, "    {}: {} button(s), {} page(s)", id, buttons, pages)?;
+        }
+        writeln!(f)?;
+
+        writeln!(f, "Duplicate words: {}", self.duplicates.len())?;
+        for (label, folders) in self.duplicates.iter().take(EXAMPLES) {
+            writeln!(f, "    {:?}: {}", label, folders.join(", "))?;
+        }
+        more(f, self.duplicates.len())?;
+
+        writeln!(f, "Words without a pronunciation: {} of {}", self.unpronounced.len(), self.words)?;
+        for label in self.unpronounced.iter().take(EXAMPLES) {
+            writeln!(f, "    {:?}", label)?;
+        }
+        more(f, self.unpronounced.len())
+    }
+}
+
+#[test]
+fn test_stats() {
+    let system = crate::format::from_text("#sgs\nname = \"Stats\"\nrows = 1\ncols = 3\n\
+        :Home\n\t\"hi\" = \"hai\"\t\"bye\"\t\"More\" -> Home::More\n.\n\
+        :Home::More\n\t\"bye\"\t\"a\"\t\"b\"\n\t\"c\"\n.\n").unwrap();
+    let stats = Stats::new(&system);
+
+    assert_eq!(vec![("Home".to_string(), 3, 1), ("Home::More".to_string(), 4, 2)], stats.folders);
+    assert_eq!((1, 7, 6), (stats.toplevel, stats.buttons(), stats.words));
+    assert_eq!(vec!["Home", "Home::More"], stats.duplicates["bye"]);
+    assert_eq!(1, stats.duplicates.len());
+    assert_eq!(5, stats.unpronounced.len());
+    assert!(stats.to_string().contains("Home::More: 4 button(s), 2 page(s)"));
+}
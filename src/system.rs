@@ -1,7 +1,15 @@
 use crate::button::Button;
+use crate::action::Action;
+use crate::color::{self, Colors};
+use crate::diff::CONFLICT_MARKER;
+use crate::format::{self, Format};
+use crate::layout::Layout;
+use crate::theme::Theme;
 
+use log::warn;
 use nanoserde::{DeJson, DeJsonErr, SerJson};
 
+use std::cmp;
 use std::collections::hash_map::HashMap;
 
 #[derive(Clone, Debug, Default, DeJson, SerJson, PartialEq)]
@@ -13,6 +21,10 @@ pub struct Folder {
     pub rows: usize,
     pub cols: usize,
     pub buttons: Vec<Option<Button>>,
+    // Defaults for buttons in this folder and its sub-folders.
+    pub background: Option<String>,
+    pub border: Option<String>,
+    pub category: Option<String>,
 }
 
 #[derive(Clone, Debug, Default, DeJson, SerJson, PartialEq)]
@@ -22,7 +34,7 @@ pub struct Hotbar {
     pub buttons: Vec<Option<Button>>,
 }
 
-#[derive(Clone, Debug, Default, DeJson, SerJson)]
+#[derive(Clone, Debug, Default, DeJson, SerJson, PartialEq)]
 pub struct System {
     pub name: String,
     pub description: String,
@@ -30,6 +42,17 @@ pub struct System {
     pub hotbar: Hotbar,
     pub variants: HashMap<String, Vec<Button>>,
     pub related: HashMap<String, Vec<Button>>,
+    // Named lists of actions, run by "macro: NAME" buttons.
+    #[nserde(default)]
+    pub macros: HashMap<String, Vec<String>>,
+    // Colors for each category, e.g. the Fitzgerald Key: "pronoun" => yellow, "verb" => green.
+    #[nserde(default)]
+    pub color_scheme: HashMap<String, Colors>,
+    // Extra themes to offer alongside the presets.
+    #[nserde(default)]
+    pub themes: Vec<Theme>,
+    // How to arrange the screen, unless a user has their own layout.
+    pub layout: Option<Layout>,
 }
 
 impl Hotbar {
@@ -56,45 +79,119 @@ impl Hotbar {
 
 impl Folder {
     pub fn get_button(&self, current_page: usize, col: usize, row: usize) -> Option<&Button> {
-        let offset = current_page * self.rows * self.cols;
-        let position = row * self.cols + col;
-
-        if let Some(btn) = self.buttons.get(offset + position) {
-            return btn.as_ref();
-        } else {
-            return None;
-        }
+        self.buttons.get(self.index_of(current_page, col, row))?.as_ref()
     }
 
     pub fn needs_pagination(&self) -> bool {
         self.buttons.len() > (self.rows * self.cols)
     }
 
+    // Every page is a full grid, so adding buttons never moves the ones already there.
+    // "->" is drawn beside Back and Home, not in the grid.
+    pub fn buttons_per_page(&self) -> usize {
+        self.rows * self.cols
+    }
+
+    pub fn pages(&self) -> usize {
+        let per_page = self.buttons_per_page();
+        if per_page == 0 {
+            return 1;
+        }
+
+        cmp::max(1, (self.buttons.len() + per_page - 1) / per_page)
+    }
+
+    pub fn index_of(&self, page: usize, col: usize, row: usize) -> usize {
+        page * self.buttons_per_page() + row * self.cols + col
+    }
+
+    /// Returns the (page, col, row) that the button at `index` is drawn at.
+    pub fn position_of(&self, index: usize) -> (usize, usize, usize) {
+        let per_page = cmp::max(1, self.buttons_per_page());
+        let cols = cmp::max(1, self.cols);
+        let page = index / per_page;
+        let position = index % per_page;
+
+        (page, position % cols, position / cols)
+    }
+
     pub fn next_page(&self, current_page: usize) -> usize {
         if !self.needs_pagination() {
             return 0;
         }
 
-        let skipped = (self.rows * self.cols) * (current_page + 1);
-        if skipped < self.buttons.len() {
+        if current_page + 1 < self.pages() {
             current_page + 1
         } else {
             0
         }
     }
-
 }
 
 impl System {
     pub fn load_file(path: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        let contents = std::fs::read_to_string(path)?;
-        Ok(Self::load_str(&contents)?)
+        Self::parse(&std::fs::read_to_string(path)?)
+    }
+
+    /// Loads a System in whichever format `contents` is in.
+    pub fn parse(contents: &str) -> Result<Self, Box<dyn std::error::Error>> {
+        match Format::detect(contents) {
+            Format::Json => Ok(Self::load_str(contents)?),
+            Format::Text => Ok(format::from_text(contents)?),
+        }
+    }
+
+    /// Like [`System::parse`], but fails if the app couldn't show the System at all, e.g.
+    /// one uploaded by someone else. Lesser problems are only logged.
+    pub fn load_checked(contents: &str) -> Result<Self, String> {
+        let system = Self::parse(contents).map_err(|err| err.to_string())?;
+        let problems = system.problems();
+        // A folder under a missing top-level folder can't be opened without crashing.
+        let unshowable = system.folders.iter().any(|f| f.rows == 0 || f.cols == 0 || !system.has_toplevel(f));
+        if system.toplevel_folders().is_empty() || unshowable {
+            return Err(problems.join("; "));
+        }
+        for problem in problems {
+            warn!("System problem: {}", problem);
+        }
+        Ok(system)
     }
 
     pub fn load_str(json: &str) -> Result<System, DeJsonErr> {
         DeJson::deserialize_json(json)
     }
 
+    /// Writes the System to `path`, first copying whatever was there to `path.bak`.
+    /// It's written in the format of what was there, or else the one `path`'s extension
+    /// is for. Fails rather than leave out anything the text format can't hold.
+    pub fn save_file(&self, path: &str) -> Result<(), Box<dyn std::error::Error>> {
+        let format = std::fs::read_to_string(path).map(|contents| Format::detect(&contents)).unwrap_or_else(|_| Format::from_path(path));
+        let contents = match format {
+            Format::Json => self.serialize_json(),
+            Format::Text => {
+                let (text, lost) = format::to_text(self);
+                if !lost.is_empty() {
+                    return Err(format!("the #sgs text format can't hold these, so save to a .json file instead: {}", lost.join(", ")).into());
+                }
+                text
+            },
+        };
+
+        if std::path::Path::new(path).exists() {
+            std::fs::copy(path, format!("{}.bak", path))?;
+        }
+        std::fs::write(path, contents)?;
+        Ok(())
+    }
+
+    /// The largest rows and columns of any folder. Buttons are sized for this grid
+    /// everywhere, so opening a smaller folder doesn't resize (and move) anything.
+    pub fn grid_size(&self) -> (usize, usize) {
+        let rows = self.folders.iter().map(|f| f.rows).max().unwrap_or(1);
+        let cols = self.folders.iter().map(|f| f.cols).max().unwrap_or(1);
+        (rows, cols)
+    }
+
     pub fn toplevel_folders(&self) -> Vec<&Folder> {
         let mut folders = vec![];
         for folder in &self.folders {
@@ -106,6 +203,176 @@ impl System {
         folders
     }
 
+    pub fn folder_index(&self, id: &str) -> Option<usize> {
+        self.folders.iter().position(|f| f.id == id)
+    }
+
+    /// The folder IDs leading to `folder`, paired with display names,
+    /// e.g. "Nouns::Animals" gives [("Nouns", "Nouns"), ("Nouns::Animals", "Animals")].
+    pub fn breadcrumb(&self, folder: usize) -> Vec<(String, String)> {
+        let mut id = String::new();
+        let mut crumbs = vec![];
+        for part in self.folders[folder].id.split("::") {
+            if !id.is_empty() {
+                id.push_str("::");
+            }
+            id.push_str(part);
+
+            let name = self.folder_index(&id).map(|idx| self.folders[idx].name.clone()).unwrap_or(part.to_string());
+            crumbs.push((id.clone(), name));
+        }
+
+        crumbs
+    }
+
+    fn category_colors(&self, category: &Option<String>) -> Colors {
+        category.as_ref().and_then(|c| self.color_scheme.get(c)).cloned().unwrap_or_default()
+    }
+
+    /// Colors for `folder`, falling back to its parent folders ("Nouns::Animals", then "Nouns").
+    pub fn folder_colors(&self, folder: usize) -> Colors {
+        let mut colors = Colors::default();
+        for (id, _) in self.breadcrumb(folder).iter().rev() {
+            if let Some(f) = self.folder_index(id).map(|idx| &self.folders[idx]) {
+                colors.inherit(&Colors { background: f.background.clone(), border: f.border.clone(), text: None });
+                colors.inherit(&self.category_colors(&f.category));
+            }
+        }
+
+        colors
+    }
+
+    /// Colors for `button` in `folder` (None for the hotbar). Folder links look like the folder they open.
+    pub fn button_colors(&self, folder: Option<usize>, button: &Button) -> Colors {
+        let mut colors = button.colors();
+        colors.inherit(&self.category_colors(&button.category));
+
+        let owner = button.folder.as_ref().and_then(|id| self.folder_index(id)).or(folder);
+        if let Some(owner) = owner {
+            colors.inherit(&self.folder_colors(owner));
+        }
+
+        colors
+    }
+
+    /// Describes colors that are invalid, or that make labels hard to read.
+    pub fn color_problems(&self) -> Vec<String> {
+        fn check(problems: &mut Vec<String>, place: &str, colors: &Colors) {
+            for (what, value) in [("background", &colors.background), ("border", &colors.border), ("text", &colors.text)] {
+                if let Some(value) = value {
+                    if color::parse_hex(value).is_none() {
+                        problems.push(format!("{}: invalid {} color '{}'", place, what, value));
+                    }
+                }
+            }
+
+            if let (Some(text), Some(background)) = (colors.text.as_ref().and_then(|t| color::parse_hex(t)), colors.background()) {
+                let ratio = color::contrast_ratio(text, background);
+                if ratio < color::MIN_CONTRAST {
+                    problems.push(format!("{}: text contrast is {:.1}:1, needs at least {}:1", place, ratio, color::MIN_CONTRAST));
+                }
+            }
+        }
+
+        let mut problems = vec![];
+        let check_category = |problems: &mut Vec<String>, place: &str, category: &Option<String>| {
+            if let Some(category) = category {
+                if !self.color_scheme.contains_key(category) {
+                    problems.push(format!("{}: unknown color category '{}'", place, category));
+                }
+            }
+        };
+
+        for (name, colors) in &self.color_scheme {
+            check(&mut problems, &format!("color scheme '{}'", name), colors);
+        }
+
+        for folder in &self.folders {
+            let place = format!("folder '{}'", folder.id);
+            check(&mut problems, &place, &Colors { background: folder.background.clone(), border: folder.border.clone(), text: None });
+            check_category(&mut problems, &place, &folder.category);
+
+            for button in folder.buttons.iter().flatten() {
+                let place = format!("button '{}' in folder '{}'", button.label, folder.id);
+                check(&mut problems, &place, &button.colors());
+                check_category(&mut problems, &place, &button.category);
+            }
+        }
+
+        problems
+    }
+
+    /// Describes anything that's broken or would misbehave: links to folders that don't
+    /// exist, invalid actions, and so on. Includes [`System::color_problems`].
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = vec![];
+
+        if self.toplevel_folders().is_empty() {
+            problems.push("no top-level folders".to_string());
+        }
+
+        for (idx, folder) in self.folders.iter().enumerate() {
+            let place = format!("folder '{}'", folder.id);
+            if self.folder_index(&folder.id) != Some(idx) {
+                problems.push(format!("{}: duplicate folder id", place));
+            }
+            if folder.rows == 0 || folder.cols == 0 {
+                problems.push(format!("{}: has {} rows and {} columns", place, folder.rows, folder.cols));
+            }
+            if !self.has_toplevel(folder) {
+                let toplevel = folder.id.split("::").next().unwrap_or(&folder.id);
+                problems.push(format!("{}: '{}' is not a top-level folder", place, toplevel));
+            }
+        }
+
+        let buttons = self.folders.iter()
+            .flat_map(|f| f.buttons.iter().flatten().map(move |b| (format!("button '{}' in folder '{}'", b.label, f.id), Some(f), b)))
+            .chain(self.hotbar.buttons.iter().flatten().map(|b| (format!("button '{}' in the hotbar", b.label), None, b)));
+        for (place, folder, button) in buttons {
+            if button.label.starts_with(CONFLICT_MARKER) {
+                problems.push(format!("{}: unresolved merge conflict", place));
+            }
+            if let Some(target) = &button.folder {
+                if self.folder_index(target).is_none() {
+                    problems.push(format!("{}: links to missing folder '{}'", place, target));
+                }
+            }
+            if let (Some(folder), Some(parent)) = (folder, &button.parent) {
+                if *parent != folder.id {
+                    problems.push(format!("{}: parent is '{}'", place, parent));
+                }
+            }
+            if let Some(action) = &button.action {
+                self.action_problems(&mut problems, &place, action);
+            }
+        }
+
+        for (name, steps) in &self.macros {
+            for step in steps {
+                self.action_problems(&mut problems, &format!("macro '{}'", name), step);
+            }
+        }
+
+        problems.extend(self.color_problems());
+        problems
+    }
+
+    fn action_problems(&self, problems: &mut Vec<String>, place: &str, action: &str) {
+        match Action::parse(action) {
+            None => problems.push(format!("{}: invalid action '{}'", place, action)),
+            Some(Action::RunMacro(name)) if !self.macros.contains_key(&name) => {
+                problems.push(format!("{}: unknown macro '{}'", place, name));
+            },
+            _ => {},
+        }
+    }
+
+    // Whether the first part of `folder`'s id (before any ::) names a top-level folder.
+    fn has_toplevel(&self, folder: &Folder) -> bool {
+        let toplevel = folder.id.split("::").next().unwrap_or(&folder.id);
+        self.toplevel_folders().iter().any(|f| f.id == toplevel)
+    }
+
     pub fn toplevel_folder_for(&self, folder: usize) -> usize {
         let folder_id = &self.folders[folder].id;
         // Split at ::, store the first item (if it exists) or the entire string (if it doesn't).
@@ -214,3 +481,133 @@ fn test_system() {
     assert_eq!("Hello!", &system.variants["hello"][1].label);
     assert_eq!("hey", &system.related["hello"][2].label);
 }
+
+#[test]
+fn test_save_file() {
+    let path = std::env::temp_dir().join(format!("sgs-test-save-{}.json", std::process::id()));
+    let path = path.to_str().unwrap();
+
+    let mut system = System::load_str(include_str!("../system-wiki2016.json")).unwrap();
+    system.save_file(path).unwrap();
+    system.name = "Edited".to_string();
+    system.save_file(path).unwrap();
+
+    assert_eq!("Edited", System::load_file(path).unwrap().name);
+    assert_eq!("Wikipedia 2016 Top 10k", System::load_file(&format!("{}.bak", path)).unwrap().name);
+
+    std::fs::remove_file(path).unwrap();
+    std::fs::remove_file(format!("{}.bak", path)).unwrap();
+
+    // A text-format System stays in the text format, whatever the file's called.
+    let path = std::env::temp_dir().join(format!("sgs-test-save-{}-text.json", std::process::id()));
+    let path = path.to_str().unwrap();
+    std::fs::write(path, "#sgs\nrows = 1\ncols = 2\n:Home\n\t\"hi\"\n.\n").unwrap();
+    let mut system = System::load_file(path).unwrap();
+    system.name = "Edited".to_string();
+    system.save_file(path).unwrap();
+    assert!(std::fs::read_to_string(path).unwrap().starts_with("#sgs"));
+    assert_eq!("Edited", System::load_file(path).unwrap().name);
+
+    // Nothing is left out, or overwritten, for lack of a place in the text format.
+    system.folders[0].buttons[0].as_mut().unwrap().image = Some("hi.png".to_string());
+    assert!(system.save_file(path).unwrap_err().to_string().contains("button images: 1"));
+    assert_eq!("Edited", System::load_file(path).unwrap().name);
+
+    std::fs::remove_file(path).unwrap();
+    std::fs::remove_file(format!("{}.bak", path)).unwrap();
+}
+
+#[test]
+fn test_pagination() {
+    let mut folder = Folder { rows: 2, cols: 3, ..Default::default() };
+    folder.buttons = (0..6).map(|i| Some(Button::new(i.to_string(), None, None))).collect();
+
+    assert!(!folder.needs_pagination());
+    assert_eq!(1, folder.pages());
+    assert_eq!((0, 2, 1), folder.position_of(5));
+    assert_eq!("5", folder.get_button(0, 2, 1).unwrap().label);
+
+    // One more button starts page 2, leaving page 1 as it was.
+    folder.buttons.push(Some(Button::new("6".to_string(), None, None)));
+    assert!(folder.needs_pagination());
+    assert_eq!(6, folder.buttons_per_page());
+    assert_eq!(2, folder.pages());
+    assert_eq!((0, 2, 1), folder.position_of(5));
+    assert_eq!((1, 0, 0), folder.position_of(6));
+    assert_eq!("6", folder.get_button(1, 0, 0).unwrap().label);
+    assert_eq!(1, folder.next_page(0));
+    assert_eq!(0, folder.next_page(1));
+
+    for index in 0..folder.buttons.len() {
+        let (page, col, row) = folder.position_of(index);
+        assert_eq!(index, folder.index_of(page, col, row));
+    }
+}
+
+#[test]
+fn test_color_scheme() {
+    let system = System::load_str(include_str!("../system-wiki2016.json")).unwrap();
+    assert!(system.color_problems().is_empty(), "{:?}", system.color_problems());
+
+    // Sub-folders, and the buttons that open them, pick up their top-level folder's category.
+    let nouns = system.folder_index("Nouns").unwrap();
+    let link = system.folders[nouns].buttons.iter().flatten().find(|b| b.folder.is_some()).unwrap();
+    let linked = system.folder_index(link.folder.as_ref().unwrap()).unwrap();
+    assert_eq!(Some("#ffb86b".to_string()), system.folder_colors(linked).background);
+    assert_eq!(Some("#ffb86b".to_string()), system.button_colors(Some(nouns), link).background);
+
+    let mut button = Button::new("no".to_string(), None, None);
+    button.category = Some("negation".to_string());
+    button.border = Some("#000".to_string());
+    let colors = system.button_colors(Some(nouns), &button);
+    assert_eq!(Some("#ff8a80".to_string()), colors.background);
+    assert_eq!(Some("#000".to_string()), colors.border);
+
+    let mut system = system;
+    system.folders[nouns].background = Some("orange".to_string());
+    system.color_scheme.get_mut("verb").unwrap().text = Some("#9bdb8a".to_string());
+    assert_eq!(2, system.color_problems().len());
+}
+
+#[test]
+fn test_problems() {
+    let system = System::load_str(include_str!("../system-wiki2016.json")).unwrap();
+    assert!(system.problems().is_empty(), "{:?}", system.problems());
+
+    let mut system = system;
+    let nouns = system.folder_index("Nouns").unwrap();
+    let mut button = Button::new("broken".to_string(), None, None);
+    button.folder = Some("Nouns::Nope".to_string());
+    button.action = Some("macro: nope".to_string());
+    system.folders[nouns].buttons.push(Some(button));
+    let mut duplicate = system.folders[nouns].clone();
+    duplicate.id = "Nope::Nouns".to_string();
+    duplicate.buttons.clear();
+    system.folders.push(duplicate.clone());
+    system.folders.push(duplicate);
+    assert_eq!(vec![
+        "folder 'Nope::Nouns': 'Nope' is not a top-level folder",
+        "folder 'Nope::Nouns': duplicate folder id",
+        "folder 'Nope::Nouns': 'Nope' is not a top-level folder",
+        "button 'broken' in folder 'Nouns': links to missing folder 'Nouns::Nope'",
+        "button 'broken' in folder 'Nouns': unknown macro 'nope'",
+    ], system.problems());
+}
+
+#[test]
+fn test_load_checked() {
+    // Minor problems are fine, as are both formats.
+    let system = System::load_checked("#sgs\nrows = 1\ncols = 1\n:Home\n\t\"Go\" -> Nowhere\n.\n").unwrap();
+    assert_eq!("Home", system.folders[0].id);
+    assert!(System::load_checked(&system.serialize_json()).is_ok());
+
+    assert!(System::load_checked("not a system").is_err());
+    let mut system = system;
+    system.folders[0].rows = 0;
+    let err = System::load_checked(&system.serialize_json()).unwrap_err();
+    assert!(err.contains("has 0 rows"), "{}", err);
+
+    // Opening this folder would look for a top-level "Food".
+    let err = System::load_checked("#sgs\nrows = 1\ncols = 1\n:Home\n\t\"Go\" -> Food::Fruit\n.\n:Food::Fruit\n\t\"apple\"\n.\n").unwrap_err();
+    assert!(err.contains("'Food' is not a top-level folder"), "{}", err);
+}
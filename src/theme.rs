@@ -0,0 +1,232 @@
+use eframe::egui::{self, Color32, FontFamily, Stroke, TextStyle};
+use nanoserde::{DeJson, SerJson};
+
+use crate::color::{self, parse_hex};
+
+use std::collections::BTreeMap;
+
+/// How the app looks: colors, fonts, and how selected/highlighted buttons stand out.
+///
+/// Besides the presets below, a System can define its own themes (e.g. a palette
+/// for someone with cortical visual impairment). Colors are "#RRGGBB" strings;
+/// anything left unset uses egui's light or dark defaults.
+#[derive(Clone, Debug, DeJson, SerJson, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    #[nserde(default)]
+    pub dark: bool,
+    /// Multiplies every text size. 1.0 is egui's default.
+    #[nserde(default = "1.0")]
+    pub text_scale: f32,
+    /// "proportional" or "monospace".
+    pub font_family: Option<String>,
+    /// A .ttf or .otf file to use instead of egui's bundled font (native only).
+    pub font_file: Option<String>,
+    pub background: Option<String>,
+    pub button: Option<String>,
+    pub text: Option<String>,
+    pub border: Option<String>,
+    #[nserde(default = "1.0")]
+    pub border_width: f32,
+    /// Fill for selected buttons, e.g. the open folder in the folder selector.
+    pub selected: Option<String>,
+    #[nserde(default = "4.0")]
+    pub selected_border_width: f32,
+    /// Outline for buttons we're drawing attention to (search results, modeling, scanning).
+    pub highlight: Option<String>,
+    #[nserde(default = "3.0")]
+    pub highlight_width: f32,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            name: "default".to_string(),
+            dark: false,
+            text_scale: 1.0,
+            font_family: None,
+            font_file: None,
+            background: None,
+            button: None,
+            text: None,
+            border: None,
+            border_width: 1.0,
+            selected: None,
+            selected_border_width: 4.0,
+            highlight: None,
+            highlight_width: 3.0,
+        }
+    }
+}
+
+/// The themes every System gets.
+pub fn presets() -> Vec<Theme> {
+    let hex = |s: &str| Some(s.to_string());
+
+    vec![
+        Theme::default(),
+        Theme {
+            name: "dark".to_string(),
+            dark: true,
+            ..Default::default()
+        },
+        Theme {
+            name: "high-contrast".to_string(),
+            dark: true,
+            background: hex("#000000"),
+            button: hex("#000000"),
+            text: hex("#ffffff"),
+            border: hex("#ffffff"),
+            border_width: 2.0,
+            selected: hex("#ffff00"),
+            selected_border_width: 6.0,
+            highlight: hex("#00ffff"),
+            highlight_width: 5.0,
+            ..Default::default()
+        },
+        Theme {
+            name: "low-vision".to_string(),
+            text_scale: 1.6,
+            background: hex("#ffffff"),
+            button: hex("#f0f0f0"),
+            text: hex("#000000"),
+            border: hex("#000000"),
+            border_width: 2.0,
+            selected: hex("#0050c8"),
+            selected_border_width: 6.0,
+            highlight: hex("#d00000"),
+            highlight_width: 5.0,
+            ..Default::default()
+        },
+    ]
+}
+
+/// Looks up a theme by name, preferring the System's own themes over the presets.
+pub fn find(name: &str, custom: &[Theme]) -> Option<Theme> {
+    custom.iter().find(|t| t.name == name).cloned()
+        .or_else(|| presets().into_iter().find(|t| t.name == name))
+}
+
+/// Every theme that can be picked: the presets, then the System's own.
+pub fn all(custom: &[Theme]) -> Vec<Theme> {
+    let mut themes: Vec<Theme> = presets().into_iter().filter(|p| !custom.iter().any(|c| c.name == p.name)).collect();
+    themes.extend(custom.iter().cloned());
+    themes
+}
+
+impl Theme {
+    pub fn visuals(&self) -> egui::Visuals {
+        let mut visuals = if self.dark { egui::Visuals::dark() } else { egui::Visuals::light() };
+
+        if let Some(background) = self.background.as_deref().and_then(parse_hex) {
+            visuals.panel_fill = background;
+            visuals.window_fill = background;
+            visuals.extreme_bg_color = background;
+        }
+
+        let text = self.text.as_deref().and_then(parse_hex);
+        let button = self.button.as_deref().and_then(parse_hex);
+        let border = self.border.as_deref().and_then(parse_hex);
+        let widgets = &mut visuals.widgets;
+        for widget in [&mut widgets.noninteractive, &mut widgets.inactive, &mut widgets.hovered, &mut widgets.active, &mut widgets.open] {
+            if let Some(text) = text {
+                widget.fg_stroke.color = text;
+            }
+            if let Some(border) = border {
+                widget.bg_stroke.color = border;
+            }
+            widget.bg_stroke.width = widget.bg_stroke.width.max(self.border_width);
+        }
+        if let Some(button) = button {
+            widgets.inactive.weak_bg_fill = button;
+            widgets.inactive.bg_fill = button;
+        }
+
+        if let Some(selected) = self.selected.as_deref().and_then(parse_hex) {
+            visuals.selection.bg_fill = selected;
+            visuals.selection.stroke.color = color::text_color_for(selected);
+        }
+
+        visuals
+    }
+
+    pub fn highlight_color(&self, visuals: &egui::Visuals) -> Color32 {
+        self.highlight.as_deref().and_then(parse_hex).unwrap_or(visuals.selection.stroke.color)
+    }
+
+    /// Outline for a selected button whose fill comes from the color scheme.
+    pub fn selected_stroke(&self, visuals: &egui::Visuals) -> Stroke {
+        Stroke::new(self.selected_border_width, visuals.selection.bg_fill)
+    }
+
+    fn family(&self) -> FontFamily {
+        match self.font_family.as_deref() {
+            Some("monospace") => FontFamily::Monospace,
+            _ => FontFamily::Proportional,
+        }
+    }
+
+    pub fn text_styles(&self) -> BTreeMap<TextStyle, egui::FontId> {
+        let mut text_styles = egui::Style::default().text_styles;
+        for font_id in text_styles.values_mut() {
+            font_id.size *= self.text_scale;
+            font_id.family = self.family();
+        }
+
+        text_styles
+    }
+
+    pub fn fonts(&self) -> egui::FontDefinitions {
+        let mut fonts = egui::FontDefinitions::default();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(path) = &self.font_file {
+            match std::fs::read(path) {
+                Ok(data) => {
+                    fonts.font_data.insert(path.clone(), egui::FontData::from_owned(data));
+                    fonts.families.entry(self.family()).or_default().insert(0, path.clone());
+                },
+                Err(err) => log::warn!("Could not load font '{}': {}", path, err),
+            }
+        }
+
+        // For letters the fonts above don't have, i.e. Arabic.
+        fonts.font_data.insert("arabic".to_string(), egui::FontData::from_static(crate::i18n::ARABIC_FONT));
+        for family in [FontFamily::Proportional, FontFamily::Monospace] {
+            fonts.families.entry(family).or_default().push("arabic".to_string());
+        }
+
+        fonts
+    }
+
+    pub fn apply(&self, ctx: &egui::Context) {
+        ctx.set_visuals(self.visuals());
+        ctx.set_fonts(self.fonts());
+        ctx.style_mut(|style| style.text_styles = self.text_styles());
+    }
+}
+
+#[test]
+fn test_theme() {
+    let names: Vec<String> = presets().into_iter().map(|t| t.name).collect();
+    assert_eq!(vec!["default", "dark", "high-contrast", "low-vision"], names);
+
+    let custom = Theme::deserialize_json(r##"{"name": "cvi", "dark": true, "background": "#000000", "selected": "#ff0000"}"##).unwrap();
+    assert_eq!(1.0, custom.text_scale);
+    assert_eq!(4.0, custom.selected_border_width);
+
+    let custom = vec![custom, Theme { name: "dark".to_string(), text_scale: 2.0, ..Default::default() }];
+    assert_eq!(Some("cvi".to_string()), find("cvi", &custom).map(|t| t.name));
+    assert_eq!(2.0, find("dark", &custom).unwrap().text_scale);
+    assert_eq!(None, find("nope", &custom));
+    assert_eq!(5, all(&custom).len());
+
+    // Selected buttons keep readable labels.
+    for theme in all(&custom).iter().filter(|t| t.selected.is_some()) {
+        let visuals = theme.visuals();
+        assert!(color::contrast_ratio(visuals.selection.stroke.color, visuals.selection.bg_fill) >= color::MIN_CONTRAST);
+    }
+
+    let low_vision = find("low-vision", &[]).unwrap();
+    assert!(low_vision.text_styles()[&TextStyle::Button].size > Theme::default().text_styles()[&TextStyle::Button].size);
+}
@@ -0,0 +1,196 @@
+//! Several people sharing one device, e.g. a classroom tablet. Everything the app saves
+//! (settings, which words are shown, the lock, an imported System) is kept per user,
+//! by prefixing storage keys with the user's id.
+
+use nanoserde::{DeJson, DeJsonErr, SerJson};
+
+#[derive(Clone, Debug, DeJson, SerJson, PartialEq)]
+pub struct User {
+    pub name: String,
+    /// Prefix for this user's storage keys. Empty for the first user, so whatever was
+    /// saved before there were several users stays theirs.
+    pub id: String,
+}
+
+#[derive(Clone, Debug, DeJson, SerJson, PartialEq)]
+pub struct Users {
+    #[nserde(default)]
+    pub users: Vec<User>,
+    /// Index of whoever used the device last.
+    #[nserde(default)]
+    pub current: usize,
+    /// Ask who's using the device each time the app starts.
+    #[nserde(default)]
+    pub pick_at_launch: bool,
+    /// How many users have ever been added, so ids are never reused.
+    #[nserde(default)]
+    pub added: usize,
+}
+
+impl Default for Users {
+    fn default() -> Self {
+        Self { users: vec![User { name: "Me".to_string(), id: String::new() }], current: 0, pick_at_launch: false, added: 0 }
+    }
+}
+
+impl Users {
+    pub const STORAGE_KEY: &'static str = "users";
+
+    pub fn load_str(json: &str) -> Result<Self, DeJsonErr> {
+        let mut users: Self = DeJson::deserialize_json(json)?;
+        if users.users.is_empty() {
+            users.users = Self::default().users;
+        }
+        if users.current >= users.users.len() {
+            users.current = 0;
+        }
+        Ok(users)
+    }
+
+    /// The list of users is shared by everyone, so it isn't kept per user.
+    pub fn load(storage: Option<&dyn eframe::Storage>) -> Self {
+        let json = storage.and_then(|s| s.get_string(Self::STORAGE_KEY));
+        json.and_then(|json| Self::load_str(&json).ok()).unwrap_or_default()
+    }
+
+    pub fn save(&self, storage: &mut dyn eframe::Storage) {
+        storage.set_string(Self::STORAGE_KEY, self.serialize_json());
+    }
+
+    pub fn current(&self) -> &User {
+        &self.users[self.current]
+    }
+
+    pub fn add(&mut self, name: &str) -> Result<(), String> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err("A user needs a name.".to_string());
+        }
+        if self.users.iter().any(|u| u.name.eq_ignore_ascii_case(name)) {
+            return Err(format!("There's already a user named '{}'.", name));
+        }
+
+        // Never reused, so a new user can't inherit a removed user's things.
+        self.added += 1;
+        let id = format!("user{}", self.added + 1);
+        self.users.push(User { name: name.to_string(), id });
+        Ok(())
+    }
+
+    /// Removes a user from the list. What they saved is left in storage.
+    /// The first user can't be removed, since they own what was saved before.
+    pub fn remove(&mut self, index: usize) -> bool {
+        if index == 0 || index >= self.users.len() {
+            return false;
+        }
+        self.users.remove(index);
+        match self.current.cmp(&index) {
+            std::cmp::Ordering::Equal => self.current = 0,
+            std::cmp::Ordering::Greater => self.current -= 1,
+            std::cmp::Ordering::Less => {},
+        }
+        true
+    }
+}
+
+/// `storage`, with every key belonging to the user with id `id`.
+pub struct UserStorage<S> {
+    storage: S,
+    id: String,
+}
+
+impl<S> UserStorage<S> {
+    pub fn new(storage: S, user: &User) -> Self {
+        Self { storage, id: user.id.clone() }
+    }
+
+    fn key(&self, key: &str) -> String {
+        if self.id.is_empty() {
+            key.to_string()
+        } else {
+            format!("{}/{}", self.id, key)
+        }
+    }
+}
+
+// For loading, e.g. from `CreationContext::storage`, which can't be written to.
+impl eframe::Storage for UserStorage<&dyn eframe::Storage> {
+    fn get_string(&self, key: &str) -> Option<String> {
+        self.storage.get_string(&self.key(key))
+    }
+
+    fn set_string(&mut self, key: &str, _value: String) {
+        log::warn!("!!! Tried to save '{}' to storage that's only for loading.", key);
+    }
+
+    fn flush(&mut self) {}
+}
+
+impl eframe::Storage for UserStorage<&mut dyn eframe::Storage> {
+    fn get_string(&self, key: &str) -> Option<String> {
+        self.storage.get_string(&self.key(key))
+    }
+
+    fn set_string(&mut self, key: &str, value: String) {
+        let key = self.key(key);
+        self.storage.set_string(&key, value);
+    }
+
+    fn flush(&mut self) {
+        self.storage.flush();
+    }
+}
+
+#[cfg(test)]
+#[derive(Default)]
+struct MemoryStorage(std::collections::HashMap<String, String>);
+
+#[cfg(test)]
+impl eframe::Storage for MemoryStorage {
+    fn get_string(&self, key: &str) -> Option<String> {
+        self.0.get(key).cloned()
+    }
+
+    fn set_string(&mut self, key: &str, value: String) {
+        self.0.insert(key.to_string(), value);
+    }
+
+    fn flush(&mut self) {}
+}
+
+#[test]
+fn test_users() {
+    use eframe::Storage;
+
+    // Saved before there were several users.
+    let mut users = Users::load_str("{}").unwrap();
+    assert_eq!(Users::default(), users);
+
+    assert!(users.add(" ").is_err());
+    users.add("Sam").unwrap();
+    users.add("Alex").unwrap();
+    assert!(users.add("sam").is_err());
+    assert_eq!(vec!["", "user2", "user3"], users.users.iter().map(|u| u.id.as_str()).collect::<Vec<_>>());
+
+    users.current = 2;
+    assert!(!users.remove(0));
+    assert!(users.remove(1));
+    assert_eq!("Alex", users.current().name);
+    users.add("Robin").unwrap();
+    assert_eq!("user4", users.users[2].id);
+    users.remove(2);
+    users.add("Robin").unwrap();
+    assert_eq!("user5", users.users[2].id);
+    assert_eq!(users, Users::load_str(&users.serialize_json()).unwrap());
+
+    let mut storage = MemoryStorage::default();
+    storage.0.insert("settings".to_string(), "mine".to_string());
+    let first = users.users[0].clone();
+    let alex = users.current().clone();
+    UserStorage::new(&mut storage as &mut dyn eframe::Storage, &alex).set_string("settings", "alex's".to_string());
+
+    let storage: &dyn eframe::Storage = &storage;
+    assert_eq!(Some("mine".to_string()), UserStorage::new(storage, &first).get_string("settings"));
+    assert_eq!(Some("alex's".to_string()), UserStorage::new(storage, &alex).get_string("settings"));
+    assert_eq!(Some("alex's".to_string()), storage.get_string("user3/settings"));
+}
@@ -1,6 +1,8 @@
 #![cfg(target_arch = "wasm32")]
 
+use crate::action::Action;
 use crate::app::App;
+use crate::events::Event;
 
 use eframe::egui;
 use log;
@@ -13,6 +15,12 @@ pub struct WebHandle {
     runner: eframe::WebRunner,
 }
 
+impl WebHandle {
+    fn with_app<R>(&self, f: impl FnOnce(&mut App) -> R) -> Option<R> {
+        self.runner.app_mut::<App>().map(|mut app| f(&mut app))
+    }
+}
+
 #[wasm_bindgen]
 impl WebHandle {
     /// Installs a panic hook, then returns.
@@ -52,15 +60,100 @@ impl WebHandle {
         self.runner.destroy();
     }
 
-    /*
-    /// Example on how to call into your app from JavaScript.
+    /// Switches to the System in `contents`, in either format, e.g. one uploaded by a
+    /// page hosting SGS. It's kept for next time. If it can't be used, the app says so
+    /// and keeps the current System. `source` is shown in messages.
     #[wasm_bindgen]
-    pub fn example(&self) {
-        if let Some(app) = self.runner.app_mut::<App>() {
-            app.example();
+    pub fn load_system(&self, source: &str, contents: &str) {
+        self.with_app(|app| app.loader().push(source, Ok(contents.to_string())));
+    }
+
+    /// Like `load_system`, but fetches the System from `url`.
+    #[wasm_bindgen]
+    pub fn load_system_url(&self, url: &str) {
+        self.with_app(|app| app.loader().fetch(url));
+    }
+
+    /// Asks the user for a System file to load. Call it from a click handler, since
+    /// browsers only show file pickers in response to one.
+    #[wasm_bindgen]
+    pub fn pick_system_file(&self) {
+        self.with_app(|app| app.loader().pick_file());
+    }
+
+    /// The text in the panel, i.e. what Speak would say.
+    #[wasm_bindgen]
+    pub fn panel_text(&self) -> Option<String> {
+        self.with_app(|app| app.panel_text())
+    }
+
+    #[wasm_bindgen]
+    pub fn set_panel_text(&self, text: &str) {
+        self.with_app(|app| app.set_panel_text(text));
+    }
+
+    /// Same as pressing Speak.
+    #[wasm_bindgen]
+    pub fn speak(&self) {
+        self.with_app(|app| {
+            app.perform(&Action::Speak);
+            app.request_repaint();
+        });
+    }
+
+    /// Same as pressing Clear.
+    #[wasm_bindgen]
+    pub fn clear(&self) {
+        self.with_app(|app| {
+            app.perform(&Action::Clear);
+            app.request_repaint();
+        });
+    }
+
+    /// Opens the folder with id `folder`, e.g. "Nouns::Animals". Returns false if
+    /// there's no such folder, or it's blocked while the app is locked.
+    #[wasm_bindgen]
+    pub fn open_folder(&self, folder: &str) -> bool {
+        self.with_app(|app| app.open_folder(folder)).unwrap_or(false)
+    }
+
+    /// Offers the user a new version, e.g. once a service worker has downloaded one.
+    /// `apply` is called if they accept.
+    #[wasm_bindgen]
+    pub fn offer_update(&self, apply: js_sys::Function) {
+        self.with_app(|app| app.offer_update(Box::new(move || {
+            if let Err(err) = apply.call0(&JsValue::NULL) {
+                log::warn!("!!! Failed to apply the update: {:?}", err);
+            }
+        })));
+    }
+
+    /// Calls `callback` with an object describing each `event`, which is one of:
+    /// - "button": a button was pressed. `{label, folder}`; folder is null for the hotbar.
+    /// - "spoken": something was spoken. `{text}`
+    /// - "folder": a different folder is showing. `{folder}`
+    #[wasm_bindgen]
+    pub fn on(&self, event: &str, callback: js_sys::Function) -> Result<(), JsValue> {
+        if !Event::NAMES.contains(&event) {
+            return Err(format!("Unknown event '{}'. Expected one of: {}.", event, Event::NAMES.join(", ")).into());
         }
+
+        let name = event.to_string();
+        let listener = move |event: &Event| {
+            if event.name() != name {
+                return;
+            }
+            let details = js_sys::JSON::parse(&event.to_json()).unwrap_or(JsValue::NULL);
+            let callback = callback.clone();
+            // Once the app's done with this frame, so the callback can call back into it.
+            wasm_bindgen_futures::spawn_local(async move {
+                if let Err(err) = callback.call1(&JsValue::NULL, &details) {
+                    log::warn!("!!! An event listener failed: {:?}", err);
+                }
+            });
+        };
+        self.with_app(|app| app.on_event(Box::new(listener))).ok_or_else(|| "The app isn't running.".into())
     }
-    */
 
     /// The JavaScript can check whether or not your app has crashed:
     #[wasm_bindgen]
@@ -0,0 +1,379 @@
+//! Speech for the web build, through the browser's `window.speechSynthesis`.
+//!
+//! The browser is only reached through `Synth`, so everything else here can be
+//! tested with a mock, without a browser.
+
+use log::warn;
+
+use std::cell::Cell;
+use std::rc::Rc;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Utterance {
+    pub text: String,
+    /// Name of the voice to use, or None for the browser's default.
+    pub voice: Option<String>,
+    pub rate: f32,
+    pub pitch: f32,
+}
+
+pub trait Synth {
+    /// Names of the voices loaded so far. Browsers load them in the background, so
+    /// this is often empty right after the page loads.
+    fn voices(&self) -> Vec<String>;
+    /// Queues `utterance`. `done` is called once it ends, or fails (e.g. it's cancelled).
+    fn speak(&mut self, utterance: &Utterance, done: Box<dyn FnMut()>) -> Result<(), String>;
+    fn cancel(&mut self);
+    fn on_voices_changed(&mut self, callback: Box<dyn FnMut()>);
+}
+
+pub struct WebSpeech {
+    synth: Box<dyn Synth>,
+    /// Name of the chosen voice. It's looked up each time something is spoken,
+    /// since it may not have been loaded yet when it was chosen.
+    voice: Option<String>,
+    pub rate: f32,
+    pub pitch: f32,
+    // One flag per utterance we've queued, set once it's done.
+    queued: Vec<Rc<Cell<bool>>>,
+    on_change: Option<Rc<dyn Fn()>>,
+}
+
+impl WebSpeech {
+    pub fn new(synth: Box<dyn Synth>) -> Self {
+        Self { synth, voice: None, rate: 1.0, pitch: 1.0, queued: vec![], on_change: None }
+    }
+
+    #[cfg(target_arch = "wasm32")]
+    pub fn browser() -> Result<Self, String> {
+        Ok(Self::new(Box::new(Browser::new()?)))
+    }
+
+    pub fn voices(&self) -> Vec<String> {
+        self.synth.voices()
+    }
+
+    fn find_voice(&self, name: &str) -> Option<String> {
+        self.synth.voices().into_iter().find(|v| v.eq_ignore_ascii_case(name))
+    }
+
+    pub fn set_voice(&mut self, voice: &str) -> Result<(), String> {
+        // With no voices loaded yet, there's nothing to check it against.
+        if !self.synth.voices().is_empty() && self.find_voice(voice).is_none() {
+            return Err(format!("No voice named '{}'", voice));
+        }
+        self.voice = Some(voice.to_string());
+        Ok(())
+    }
+
+    pub fn speak(&mut self, text: &str) -> Result<(), String> {
+        let voice = self.voice.as_deref().and_then(|name| {
+            let found = self.find_voice(name);
+            if found.is_none() {
+                warn!("!!! Voice '{}' isn't loaded (yet), using the default voice.", name);
+            }
+            found
+        });
+        let utterance = Utterance { text: text.to_string(), voice, rate: self.rate, pitch: self.pitch };
+
+        let finished = Rc::new(Cell::new(false));
+        let done = {
+            let finished = finished.clone();
+            let on_change = self.on_change.clone();
+            Box::new(move || {
+                finished.set(true);
+                if let Some(on_change) = &on_change {
+                    on_change();
+                }
+            })
+        };
+        self.synth.speak(&utterance, done)?;
+
+        self.queued.retain(|f| !f.get());
+        self.queued.push(finished);
+        Ok(())
+    }
+
+    pub fn stop(&mut self) {
+        self.synth.cancel();
+        self.queued.clear();
+    }
+
+    pub fn is_speaking(&mut self) -> bool {
+        self.queued.retain(|f| !f.get());
+        !self.queued.is_empty()
+    }
+
+    /// Calls `callback` when something finishes being spoken or more voices are loaded,
+    /// e.g. to redraw the Speak/Stop button.
+    pub fn on_change(&mut self, callback: impl Fn() + 'static) {
+        let callback: Rc<dyn Fn()> = Rc::new(callback);
+        let on_voices_changed = callback.clone();
+        self.synth.on_voices_changed(Box::new(move || on_voices_changed()));
+        self.on_change = Some(callback);
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+use wasm_bindgen::{closure::Closure, JsCast};
+
+#[cfg(target_arch = "wasm32")]
+use web_sys::{SpeechSynthesis, SpeechSynthesisUtterance, SpeechSynthesisVoice};
+
+// Chrome stops sending events for utterances that get garbage collected, so they're
+// kept around, along with their event handlers, until they're done.
+#[cfg(target_arch = "wasm32")]
+struct Queued {
+    utterance: SpeechSynthesisUtterance,
+    _on_end: Closure<dyn FnMut()>,
+    finished: Rc<Cell<bool>>,
+}
+
+#[cfg(target_arch = "wasm32")]
+pub struct Browser {
+    synth: SpeechSynthesis,
+    queued: Vec<Queued>,
+    on_voices_changed: Option<Closure<dyn FnMut()>>,
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Browser {
+    pub fn new() -> Result<Self, String> {
+        let window = web_sys::window().ok_or("No window to speak from")?;
+        let synth = window.speech_synthesis().map_err(|_| "This browser can't speak (no speechSynthesis)")?;
+        Ok(Self { synth, queued: vec![], on_voices_changed: None })
+    }
+
+    fn browser_voices(&self) -> Vec<SpeechSynthesisVoice> {
+        self.synth.get_voices().iter().map(|v| v.unchecked_into()).collect()
+    }
+
+    fn forget_finished(&mut self) {
+        self.queued.retain(|queued| {
+            if queued.finished.get() {
+                // So nothing calls a handler after it's dropped.
+                queued.utterance.set_onend(None);
+                queued.utterance.set_onerror(None);
+            }
+            !queued.finished.get()
+        });
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl Synth for Browser {
+    fn voices(&self) -> Vec<String> {
+        self.browser_voices().iter().map(|v| v.name()).collect()
+    }
+
+    fn speak(&mut self, utterance: &Utterance, mut done: Box<dyn FnMut()>) -> Result<(), String> {
+        self.forget_finished();
+
+        let js_utterance = SpeechSynthesisUtterance::new_with_text(&utterance.text).map_err(|err| format!("{:?}", err))?;
+        js_utterance.set_rate(utterance.rate);
+        js_utterance.set_pitch(utterance.pitch);
+        if let Some(name) = &utterance.voice {
+            let voice = self.browser_voices().into_iter().find(|v| v.name() == *name);
+            js_utterance.set_voice(voice.as_ref());
+        }
+
+        let finished = Rc::new(Cell::new(false));
+        let on_end = {
+            let finished = finished.clone();
+            Closure::<dyn FnMut()>::new(move || {
+                if !finished.replace(true) {
+                    done();
+                }
+            })
+        };
+        js_utterance.set_onend(Some(on_end.as_ref().unchecked_ref()));
+        js_utterance.set_onerror(Some(on_end.as_ref().unchecked_ref()));
+
+        self.synth.speak(&js_utterance);
+        self.queued.push(Queued { utterance: js_utterance, _on_end: on_end, finished });
+        Ok(())
+    }
+
+    fn cancel(&mut self) {
+        self.synth.cancel();
+    }
+
+    fn on_voices_changed(&mut self, callback: Box<dyn FnMut()>) {
+        let callback = Closure::wrap(callback);
+        self.synth.set_onvoiceschanged(Some(callback.as_ref().unchecked_ref()));
+        self.on_voices_changed = Some(callback);
+    }
+}
+
+/// Stands in for the browser in tests. Voices can be loaded and utterances finished
+/// whenever the test wants, like a browser would on its own schedule.
+#[cfg(test)]
+#[derive(Default)]
+struct MockState {
+    voices: Vec<String>,
+    spoken: Vec<Utterance>,
+    queue: Vec<Box<dyn FnMut()>>,
+    on_voices_changed: Option<Box<dyn FnMut()>>,
+}
+
+#[cfg(test)]
+#[derive(Clone, Default)]
+struct MockSynth(Rc<std::cell::RefCell<MockState>>);
+
+#[cfg(test)]
+impl MockSynth {
+    fn load_voices(&self, names: &[&str]) {
+        let mut state = self.0.borrow_mut();
+        state.voices = names.iter().map(|n| n.to_string()).collect();
+        if let Some(callback) = &mut state.on_voices_changed {
+            callback();
+        }
+    }
+
+    fn finish_next(&self) {
+        let mut done = self.0.borrow_mut().queue.remove(0);
+        done();
+    }
+}
+
+#[cfg(test)]
+impl Synth for MockSynth {
+    fn voices(&self) -> Vec<String> {
+        self.0.borrow().voices.clone()
+    }
+
+    fn speak(&mut self, utterance: &Utterance, done: Box<dyn FnMut()>) -> Result<(), String> {
+        let mut state = self.0.borrow_mut();
+        state.spoken.push(utterance.clone());
+        state.queue.push(done);
+        Ok(())
+    }
+
+    fn cancel(&mut self) {
+        // Browsers report cancelled utterances as errors.
+        let queue = std::mem::take(&mut self.0.borrow_mut().queue);
+        for mut done in queue {
+            done();
+        }
+    }
+
+    fn on_voices_changed(&mut self, callback: Box<dyn FnMut()>) {
+        self.0.borrow_mut().on_voices_changed = Some(callback);
+    }
+}
+
+#[test]
+fn test_web_speech() {
+    let mock = MockSynth::default();
+    let mut speech = WebSpeech::new(Box::new(mock.clone()));
+    let changes = Rc::new(Cell::new(0));
+    speech.on_change({
+        let changes = changes.clone();
+        move || changes.set(changes.get() + 1)
+    });
+
+    // Voices haven't loaded yet, so any voice is accepted for now, and the default is used.
+    assert!(speech.voices().is_empty());
+    speech.set_voice("Alice").unwrap();
+    speech.rate = 1.5;
+    speech.speak("hello").unwrap();
+    assert_eq!(Utterance { text: "hello".to_string(), voice: None, rate: 1.5, pitch: 1.0 }, mock.0.borrow().spoken[0]);
+    assert!(speech.is_speaking());
+
+    mock.load_voices(&["alice", "Bob"]);
+    assert_eq!(1, changes.get());
+    assert!(speech.set_voice("Carol").is_err());
+    speech.speak("again").unwrap();
+    assert_eq!(Some("alice".to_string()), mock.0.borrow().spoken[1].voice);
+
+    // The Speak/Stop button flips back once everything queued has ended.
+    mock.finish_next();
+    assert!(speech.is_speaking());
+    mock.finish_next();
+    assert!(!speech.is_speaking());
+    assert_eq!(3, changes.get());
+
+    speech.speak("stop me").unwrap();
+    speech.stop();
+    assert!(!speech.is_speaking());
+    assert!(mock.0.borrow().queue.is_empty());
+}
+
+// A stand-in for the browser's speechSynthesis, installed on Node's global object, so the
+// real `Browser` synth can be tested with wasm-bindgen-test under Node.
+#[cfg(all(test, target_arch = "wasm32"))]
+#[wasm_bindgen::prelude::wasm_bindgen(inline_js = r#"
+export function install_fake_speech() {
+    class Utterance {
+        constructor(text) { this.text = text; this.rate = 1; this.pitch = 1; this.voice = null; }
+    }
+    const synth = {
+        voices: [],
+        queue: [],
+        spoken: [],
+        onvoiceschanged: null,
+        getVoices() { return this.voices; },
+        speak(utterance) { this.queue.push(utterance); this.spoken.push(utterance); },
+        // Browsers report cancelled utterances as errors.
+        cancel() { for (const u of this.queue.splice(0)) { if (u.onerror) u.onerror(); } },
+    };
+    globalThis.Window = class { static [Symbol.hasInstance](obj) { return obj === globalThis; } };
+    globalThis.SpeechSynthesisUtterance = Utterance;
+    globalThis.speechSynthesis = synth;
+}
+
+export function load_voices(names) {
+    const synth = globalThis.speechSynthesis;
+    synth.voices = names.map(name => ({ name }));
+    if (synth.onvoiceschanged) synth.onvoiceschanged();
+}
+
+export function finish_next() {
+    const utterance = globalThis.speechSynthesis.queue.shift();
+    if (utterance.onend) utterance.onend();
+}
+
+export function spoken(index) {
+    const u = globalThis.speechSynthesis.spoken[index];
+    return [u.text, u.voice ? u.voice.name : "", u.rate, u.pitch].join("|");
+}
+"#)]
+extern "C" {
+    fn install_fake_speech();
+    fn load_voices(names: Vec<js_sys::JsString>);
+    fn finish_next();
+    fn spoken(index: usize) -> String;
+}
+
+#[cfg(all(test, target_arch = "wasm32"))]
+#[wasm_bindgen_test::wasm_bindgen_test]
+fn test_browser_speech() {
+    install_fake_speech();
+    let mut speech = WebSpeech::browser().unwrap();
+    let changes = Rc::new(Cell::new(0));
+    speech.on_change({
+        let changes = changes.clone();
+        move || changes.set(changes.get() + 1)
+    });
+
+    speech.set_voice("bob").unwrap();
+    speech.pitch = 0.5;
+    speech.speak("hello").unwrap();
+    assert_eq!("hello||1|0.5", spoken(0));
+
+    load_voices(vec!["Alice".into(), "Bob".into()]);
+    assert_eq!(1, changes.get());
+    assert_eq!(vec!["Alice", "Bob"], speech.voices());
+    speech.speak("again").unwrap();
+    assert_eq!("again|Bob|1|0.5", spoken(1));
+
+    finish_next();
+    assert!(speech.is_speaking());
+    finish_next();
+    assert!(!speech.is_speaking());
+    assert_eq!(3, changes.get());
+
+    speech.speak("stop me").unwrap();
+    speech.stop();
+    assert!(!speech.is_speaking());
+}
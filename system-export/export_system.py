@@ -1,6 +1,6 @@
 #!/usr/bin/env python3
 
-from dataclasses import dataclass
+from dataclasses import dataclass, field
 from dataclasses_json import dataclass_json
 import itertools
 from pathlib import Path
@@ -15,6 +15,12 @@ class Button:
     pronunciation: Optional[str]
     image: Optional[str]
     folder: Optional[str]
+    action: Optional[str] = None
+    background: Optional[str] = None
+    border: Optional[str] = None
+    category: Optional[str] = None
+    id: Optional[str] = None
+    hidden: bool = False
 
 
 @dataclass_json
@@ -27,6 +33,16 @@ class Folder:
     rows: int
     cols: int
     buttons: list[Optional[Button]]
+    background: Optional[str] = None
+    border: Optional[str] = None
+    category: Optional[str] = None
+
+@dataclass_json
+@dataclass
+class Colors:
+    background: Optional[str] = None
+    border: Optional[str] = None
+    text: Optional[str] = None
 
 @dataclass_json
 @dataclass
@@ -44,6 +60,7 @@ class System:
     hotbar: Hotbar
     variants: dict[str, list[Button]]
     related: dict[str, list[Button]]
+    color_scheme: dict[str, Colors] = field(default_factory=dict)
 
 
 def mkbutton(label, parent=None):
@@ -6,6 +6,10 @@ cp -r ./web ./_site || exit $?
 cargo build --target wasm32-unknown-unknown || exit $?
 wasm-bindgen --target web --out-dir _site/ target/wasm32-unknown-unknown/debug/sgs.wasm || exit $?
 
+# A new version for every build that changes anything, so caches don't go stale.
+VERSION=$(cat _site/*.wasm _site/*.js _site/*.html _site/manifest.json | sha256sum | cut -c1-12)
+sed -i "s/^const VERSION = \"dev\";/const VERSION = \"$VERSION\";/" _site/fetch-worker.js || exit $?
+
 cd _site
 
 python3 -m http.server || exit $?
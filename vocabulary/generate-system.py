@@ -202,16 +202,20 @@ def set_button_parent(parent, btn):
     btn['parent'] = parent
     return btn
 
-def folder(name, buttons, toplevel=False, immediate=False, rows=6, cols=9):
-    return {
+def folder(name, buttons, toplevel=False, immediate=False, rows=6, cols=9, category=None):
+    result = {
         "name": name.split("::")[-1],
         "id": name,
         "toplevel": toplevel,
         "immediate": immediate,
         "rows": rows,
         "cols": cols,
-        "buttons": [set_button_parent(name, btn) for btn in buttons],
     }
+    # Sub-folders inherit their parent's category.
+    if category:
+        result["category"] = category
+    result["buttons"] = [set_button_parent(name, btn) for btn in buttons]
+    return result
 
 def button(word, **options):
     if 'folder' in options and options['folder']:
@@ -261,11 +265,11 @@ system = {
     "name": "Wikipedia 2016 Top 10k",
     "description": "A system generated from the top 10,000 words on Wikipedia in 2016.",
     "folders": [
-        folder('Pronouns',  buttons(folders['Pronouns']), toplevel=True),
-        folder('Nouns',     buttons(nouns_subfolders_names, folder=True), toplevel=True),
-        folder('Verbs',     buttons(verbs_subfolders_names, folder=True), toplevel=True),
-        folder('Attributes',    buttons(folders['Attributes']), toplevel=True),
-        folder('Modifiers',     buttons(folders['Modifiers']), toplevel=True),
+        folder('Pronouns',  buttons(folders['Pronouns']), toplevel=True, category='pronoun'),
+        folder('Nouns',     buttons(nouns_subfolders_names, folder=True), toplevel=True, category='noun'),
+        folder('Verbs',     buttons(verbs_subfolders_names, folder=True), toplevel=True, category='verb'),
+        folder('Attributes',    buttons(folders['Attributes']), toplevel=True, category='adjective'),
+        folder('Modifiers',     buttons(folders['Modifiers']), toplevel=True, category='adverb'),
         *nouns_subfolders,
         *verbs_subfolders,
     ],
@@ -317,6 +321,18 @@ system = {
             {"label": "planet"}
         ],
     },
+    # Modified Fitzgerald Key.
+    "color_scheme": {
+        "pronoun": {"background": "#ffe97a"},
+        "noun": {"background": "#ffb86b"},
+        "verb": {"background": "#9bdb8a"},
+        "adjective": {"background": "#8ec8ff"},
+        "adverb": {"background": "#d2b48c"},
+        "social": {"background": "#ffb3d9"},
+        "question": {"background": "#c9a7eb"},
+        "negation": {"background": "#ff8a80"},
+        "determiner": {"background": "#e0e0e0"},
+    },
 }
 
 import json
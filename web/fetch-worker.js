@@ -1,8 +1,73 @@
+// test-site.sh replaces this with a hash of the build, so each build gets its own
+// cache, and browsers see a changed worker and offer the update.
+const VERSION = "dev";
+
+const STATIC_CACHE = "sgs-static-" + VERSION;
+// Systems and images fetched while using the app. Kept across updates.
+const RUNTIME_CACHE = "sgs-runtime";
+
+// Everything needed to start without a network. The bundled system is built into the wasm.
+const PRECACHE = [
+    "./",
+    "index.html",
+    "sgs.js",
+    "sgs_bg.wasm",
+    "console.js",
+    "manifest.json",
+    "icons/192.png",
+    "icons/512.png",
+];
+
+self.addEventListener("install", event => {
+    // Not skipWaiting(): the page asks the user first, so an update never
+    // interrupts someone in the middle of saying something.
+    event.waitUntil(caches.open(STATIC_CACHE).then(cache => cache.addAll(PRECACHE)));
+});
+
+self.addEventListener("activate", event => {
+    event.waitUntil(
+        caches.keys()
+        .then(names => Promise.all(
+            names
+            .filter(name => name.startsWith("sgs-static-") && name != STATIC_CACHE)
+            .map(name => caches.delete(name))
+        ))
+        .then(() => self.clients.claim())
+    );
+});
+
+self.addEventListener("message", event => {
+    if (event.data == "skip-waiting") {
+        self.skipWaiting();
+    }
+});
+
+// Tries the network first, so changes show up, and falls back to the last copy.
+function networkFirst(request) {
+    return fetch(request)
+        .then(response => {
+            if (response.ok) {
+                let copy = response.clone();
+                caches.open(RUNTIME_CACHE).then(cache => cache.put(request, copy));
+            }
+            return response;
+        })
+        .catch(error =>
+            caches.match(request).then(cached => cached || Promise.reject(error))
+        );
+}
+
 self.addEventListener("fetch", event => {
+    let request = event.request;
+    if (request.method != "GET") {
+        return;
+    }
+
+    // Pages load with things like ?system=..., but it's the same page.
+    let ignoreSearch = request.mode == "navigate";
     event.respondWith(
-        fetch(event.request)
-        .catch(error => {
-            return caches.match(event.request) ;
-        })
+        caches.open(STATIC_CACHE)
+        .then(cache => cache.match(request, { ignoreSearch }))
+        .then(cached => cached || networkFirst(request))
     );
 });